@@ -0,0 +1,24 @@
+use std::process::Command;
+
+//the commit and whether tracked files differ from it are embedded so a binary copied elsewhere
+//still reports the sources it was built from, untracked files such as results are ignored
+fn main() {
+    let commit = git(&["rev-parse", "HEAD"]);
+    let dirty = git(&["status", "--porcelain", "--untracked-files=no"]).map(|status| !status.is_empty());
+    println!("cargo:rustc-env=GIT_COMMIT={}", commit.unwrap_or_default());
+    println!("cargo:rustc-env=GIT_DIRTY={}", dirty.map_or(String::new(), |dirty| dirty.to_string()));
+
+    //a commit moves HEAD or its branch and rewrites the index, edits change the sources
+    for path in [".git/HEAD", ".git/index", ".git/refs", "src", "Cargo.toml", "build.rs"] {
+        println!("cargo:rerun-if-changed={}", path);
+    }
+}
+
+//empty if git or the repository is not available
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+
+/// Number of equally sized histogram bins between 0 and t_max.
+pub const HISTOGRAM_BINS: usize = 50;
+
+/// Extinction times of all runs of one grid point. Runs which survived until
+/// t_max are right censored and only counted in `censored`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ExtinctionTimes {
+    pub t_max: f64,
+    pub times: Vec<f64>,
+    pub censored: usize,
+}
+
+impl ExtinctionTimes {
+    pub fn new(t_max: f64) -> Self {
+        ExtinctionTimes { t_max, times: Vec::new(), censored: 0 }
+    }
+
+    /// `died` is the result of `Sim::run`, `t` the time of the last event.
+    pub fn add(&mut self, died: bool, t: f64) {
+        if died {
+            self.times.push(t);
+        } else {
+            self.censored += 1;
+        }
+    }
+
+    pub fn merge(&mut self, other: ExtinctionTimes) {
+        self.times.extend(other.times);
+        self.censored += other.censored;
+    }
+
+    pub fn runs(&self) -> usize {
+        self.times.len() + self.censored
+    }
+
+    /// Mean extinction time of the runs that died out.
+    pub fn mean(&self) -> Option<f64> {
+        if self.times.is_empty() {
+            return None;
+        }
+        Some(self.times.iter().sum::<f64>() / self.times.len() as f64)
+    }
+
+    /// Median extinction time of the runs that died out.
+    pub fn median(&self) -> Option<f64> {
+        if self.times.is_empty() {
+            return None;
+        }
+        let times = self.sorted_times();
+        let n = times.len();
+        if n % 2 == 1 {
+            Some(times[n / 2])
+        } else {
+            Some((times[n / 2 - 1] + times[n / 2]) / 2.0)
+        }
+    }
+
+    /// Counts per bin, bin `i` covers `[i, i + 1) * t_max / bins`.
+    pub fn histogram(&self, bins: usize) -> Vec<usize> {
+        let mut counts = vec![0; bins];
+        let width = self.t_max / bins as f64;
+        for &t in &self.times {
+            let bin = ((t / width) as usize).min(bins - 1);
+            counts[bin] += 1;
+        }
+        counts
+    }
+
+    /// Kaplan-Meier estimate of the survival probability. Returns the pairs
+    /// `(t, S(t))` at every distinct extinction time, censored runs stay at
+    /// risk until t_max.
+    pub fn kaplan_meier(&self) -> Vec<(f64, f64)> {
+        let times = self.sorted_times();
+        let mut at_risk = self.runs();
+        let mut survival = 1.0;
+        let mut curve = Vec::new();
+        let mut i = 0;
+        while i < times.len() {
+            let t = times[i];
+            let mut deaths = 0;
+            while i < times.len() && times[i] == t {
+                deaths += 1;
+                i += 1;
+            }
+            survival *= 1.0 - deaths as f64 / at_risk as f64;
+            at_risk -= deaths;
+            curve.push((t, survival));
+        }
+        curve
+    }
+
+    fn sorted_times(&self) -> Vec<f64> {
+        let mut times = self.times.clone();
+        times.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        times
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extinction_times() {
+        let mut times = ExtinctionTimes::new(5.0);
+        for (died, t) in [(true, 2.0), (false, 5.0), (true, 1.0), (true, 4.0)] {
+            times.add(died, t);
+        }
+        let mut other = ExtinctionTimes::new(5.0);
+        other.add(true, 2.0);
+        other.add(false, 5.1);
+        times.merge(other);
+        assert_eq!((times.runs(), times.censored), (6, 2));
+        assert_eq!(times.mean(), Some(2.25));
+        assert_eq!(times.median(), Some(2.0));
+        assert_eq!(times.histogram(5), vec![0, 1, 2, 0, 1]);
+        assert_eq!(ExtinctionTimes::new(5.0).median(), None);
+    }
+
+    #[test]
+    fn test_kaplan_meier() {
+        //6 runs, 2 of them censored at t_max: S(1) = 5/6, S(2) = 5/6 * 3/5, S(4) = 1/2 * 2/3
+        let times = ExtinctionTimes { t_max: 5.0, times: vec![4.0, 2.0, 1.0, 2.0], censored: 2 };
+        let curve = times.kaplan_meier();
+        let expected = [(1.0, 5.0 / 6.0), (2.0, 0.5), (4.0, 1.0 / 3.0)];
+        assert_eq!(curve.len(), expected.len());
+        for ((t, s), (expected_t, expected_s)) in curve.into_iter().zip(expected) {
+            assert_eq!(t, expected_t);
+            assert!((s - expected_s).abs() < 1e-12, "S({}) = {}", t, s);
+        }
+
+        //without censoring the curve ends at 0, with only censored runs it is empty
+        let times = ExtinctionTimes { t_max: 5.0, times: vec![3.0, 1.0, 2.0], censored: 0 };
+        assert_eq!(times.median(), Some(2.0));
+        assert_eq!(times.kaplan_meier().last(), Some(&(3.0, 0.0)));
+        assert!(ExtinctionTimes { t_max: 5.0, times: Vec::new(), censored: 3 }.kaplan_meier().is_empty());
+    }
+}
@@ -0,0 +1,134 @@
+/// Result of an ordinary least squares fit `y = intercept + slope * x`.
+#[derive(Clone, Copy, Debug)]
+pub struct LinearFit {
+    pub slope: f64,
+    pub intercept: f64,
+    pub slope_std_err: f64,
+}
+
+/// Returns `None` if there are less than two points or all x values are equal.
+pub fn linear_fit(xs: &[f64], ys: &[f64]) -> Option<LinearFit> {
+    let n = xs.len().min(ys.len());
+    if n < 2 {
+        return None;
+    }
+    let mean_x = xs[..n].iter().sum::<f64>() / n as f64;
+    let mean_y = ys[..n].iter().sum::<f64>() / n as f64;
+    let mut sxx = 0.0;
+    let mut sxy = 0.0;
+    for i in 0..n {
+        sxx += (xs[i] - mean_x) * (xs[i] - mean_x);
+        sxy += (xs[i] - mean_x) * (ys[i] - mean_y);
+    }
+    if sxx == 0.0 {
+        return None;
+    }
+    let slope = sxy / sxx;
+    let intercept = mean_y - slope * mean_x;
+
+    let slope_std_err = if n > 2 {
+        let residuals: f64 = (0..n)
+            .map(|i| ys[i] - intercept - slope * xs[i])
+            .map(|r| r * r)
+            .sum();
+        (residuals / (n - 2) as f64 / sxx).sqrt()
+    } else {
+        0.0
+    };
+
+    Some(LinearFit { slope, intercept, slope_std_err })
+}
+
+/// Mean and standard error of the mean, `None` for an empty slice.
+pub fn mean_std_err(values: &[f64]) -> Option<(f64, f64)> {
+    if values.is_empty() {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    if values.len() == 1 {
+        return Some((mean, 0.0));
+    }
+    let variance = values.iter().map(|v| (v - mean) * (v - mean)).sum::<f64>() / (n - 1.0);
+    Some((mean, (variance / n).sqrt()))
+}
+
+/// Minimizes `f` with the Nelder-Mead simplex method starting at `start`,
+/// `step` is the initial size of the simplex along every coordinate.
+/// Returns the best point and its value.
+pub fn nelder_mead<F: Fn(&[f64]) -> f64>(f: F, start: &[f64], step: &[f64], iterations: usize) -> (Vec<f64>, f64) {
+    let n = start.len();
+    let mut simplex: Vec<(Vec<f64>, f64)> = Vec::with_capacity(n + 1);
+    simplex.push((start.to_vec(), f(start)));
+    for i in 0..n {
+        let mut point = start.to_vec();
+        point[i] += step[i];
+        let value = f(&point);
+        simplex.push((point, value));
+    }
+
+    for _ in 0..iterations {
+        simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+        let centroid: Vec<f64> = (0..n)
+            .map(|i| simplex[..n].iter().map(|(p, _)| p[i]).sum::<f64>() / n as f64)
+            .collect();
+        let along = |t: f64| -> Vec<f64> {
+            (0..n).map(|i| centroid[i] + t * (simplex[n].0[i] - centroid[i])).collect()
+        };
+
+        let reflected = along(-1.0);
+        let reflected_value = f(&reflected);
+        if reflected_value < simplex[0].1 {
+            let expanded = along(-2.0);
+            let expanded_value = f(&expanded);
+            simplex[n] = if expanded_value < reflected_value {
+                (expanded, expanded_value)
+            } else {
+                (reflected, reflected_value)
+            };
+        } else if reflected_value < simplex[n - 1].1 {
+            simplex[n] = (reflected, reflected_value);
+        } else {
+            let contracted = along(0.5);
+            let contracted_value = f(&contracted);
+            if contracted_value < simplex[n].1 {
+                simplex[n] = (contracted, contracted_value);
+            } else {
+                let best = simplex[0].0.clone();
+                for (point, value) in simplex.iter_mut().skip(1) {
+                    for i in 0..n {
+                        point[i] = best[i] + 0.5 * (point[i] - best[i]);
+                    }
+                    *value = f(point);
+                }
+            }
+        }
+    }
+    simplex.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    simplex.swap_remove(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_linear_fit() {
+        let xs = [0.0, 1.0, 2.0, 3.0];
+        let ys = [1.0, 3.0, 5.0, 7.0];
+        let fit = linear_fit(&xs, &ys).unwrap();
+        assert!((fit.slope - 2.0).abs() < 1e-12);
+        assert!((fit.intercept - 1.0).abs() < 1e-12);
+        assert!(fit.slope_std_err < 1e-12);
+        assert!(linear_fit(&[1.0, 1.0], &[0.0, 1.0]).is_none());
+    }
+
+    #[test]
+    fn test_nelder_mead() {
+        let rosenbrock = |p: &[f64]| (1.0 - p[0]).powi(2) + 100.0 * (p[1] - p[0] * p[0]).powi(2);
+        let (best, value) = nelder_mead(rosenbrock, &[-1.0, 2.0], &[0.5, 0.5], 2000);
+        assert!(value < 1e-8);
+        assert!((best[0] - 1.0).abs() < 1e-3);
+        assert!((best[1] - 1.0).abs() < 1e-3);
+    }
+}
@@ -0,0 +1,86 @@
+use crate::simulation::sim::{BoundingBox, Sim};
+
+use super::fit::{linear_fit, mean_std_err};
+use super::sampling::sample;
+
+/// Fraction of the run that is discarded as transient before fitting the front.
+const TRANSIENT_FRACTION: f64 = 0.5;
+
+pub struct FrontEstimate {
+    pub surviving: usize,
+    pub velocity: f64,
+    pub velocity_std_err: f64,
+}
+
+/// Distance of the infection front from the centre of the infected region.
+/// In 1D this is half the distance between leftmost and rightmost infected site,
+/// in 2D the mean of the half widths along both axes.
+pub fn front_position(bounding_box: &BoundingBox, dimensions: usize) -> f64 {
+    let width = (bounding_box.max_x - bounding_box.min_x) as f64 / 2.0;
+    if dimensions == 1 {
+        width
+    } else {
+        let height = (bounding_box.max_y - bounding_box.min_y) as f64 / 2.0;
+        (width + height) / 2.0
+    }
+}
+
+/// Fits the front position of one run against time after the transient.
+/// Returns `None` if the disease died out before t_max.
+pub fn front_velocity(sim: &mut dyn Sim, t_max: f64, dt: f64, dimensions: usize) -> Option<f64> {
+    let samples = sample(sim, t_max, dt, |sim| sim.get_bounding_box());
+    let mut ts = Vec::new();
+    let mut positions = Vec::new();
+    for (t, bounding_box) in samples {
+        let bounding_box = bounding_box?;
+        if t >= TRANSIENT_FRACTION * t_max {
+            ts.push(t);
+            positions.push(front_position(&bounding_box, dimensions));
+        }
+    }
+    linear_fit(&ts, &positions).map(|fit| fit.slope)
+}
+
+/// Averages the fitted front velocity over all runs that survive until t_max.
+/// The standard error is taken from the spread between the runs.
+pub fn estimate_velocity<F: Fn() -> Box<dyn Sim>>(new_sim: F,
+                                                   simulations: i32,
+                                                   t_max: f64,
+                                                   dt: f64,
+                                                   dimensions: usize) -> FrontEstimate {
+    let velocities: Vec<f64> = (0..simulations)
+        .filter_map(|_| front_velocity(new_sim().as_mut(), t_max, dt, dimensions))
+        .collect();
+    let (velocity, velocity_std_err) = mean_std_err(&velocities).unwrap_or((f64::NAN, f64::NAN));
+    FrontEstimate {
+        surviving: velocities.len(),
+        velocity,
+        velocity_std_err,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::registry;
+
+    #[test]
+    fn test_front_position() {
+        let bounding_box = BoundingBox { min_x: -4, max_x: 6, min_y: -1, max_y: 1 };
+        assert_eq!(front_position(&bounding_box, 1), 5.0);
+        assert_eq!(front_position(&bounding_box, 2), 3.0);
+    }
+
+    #[test]
+    fn test_front_velocity() {
+        let model = registry::find("sim1d_rb").unwrap();
+        //far above the threshold almost every run survives and the front advances with nearly
+        //the infection rate towards one side, a bit slower since the outermost node can heal
+        let seed = std::cell::Cell::new(0);
+        let estimate = estimate_velocity(|| { seed.set(seed.get() + 1); model.create_with_seed(&[6.0], seed.get()).unwrap() }, 5, 40.0, 1.0, 1);
+        assert!(estimate.surviving >= 4);
+        assert!(estimate.velocity > 3.0 && estimate.velocity < 6.6, "velocity {}", estimate.velocity);
+        //a run without infections dies at once and has no velocity
+        assert!(front_velocity(model.create_with_seed(&[0.0], 1).unwrap().as_mut(), 40.0, 1.0, 1).is_none());
+    }
+}
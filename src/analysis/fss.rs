@@ -0,0 +1,217 @@
+use crate::simulation::finite;
+use crate::simulation::sim::Sim;
+
+use super::fit::nelder_mead;
+
+const COLLAPSE_ITERATIONS: usize = 2000;
+
+/// Time integrals of the density and its powers over the measurement window.
+#[derive(Clone, Copy, Default)]
+pub struct Moments {
+    pub rho: f64,
+    pub rho2: f64,
+    pub rho4: f64,
+    pub time: f64,
+}
+
+impl Moments {
+    pub fn merge(&mut self, other: Moments) {
+        self.rho += other.rho;
+        self.rho2 += other.rho2;
+        self.rho4 += other.rho4;
+        self.time += other.time;
+    }
+}
+
+/// Runs `sim` until t_max and integrates the density over `[t_relax, t_max]`.
+/// Runs that die out only contribute the time they survived, so the averages
+/// are quasi-stationary averages.
+pub fn measure(sim: &mut finite::Simulation, t_relax: f64, t_max: f64) -> Moments {
+    let mut moments = Moments::default();
+    while sim.get_t() < t_max {
+        let t = sim.get_t();
+        let rho = sim.get_density();
+        if !sim.step() {
+            break;
+        }
+        let dt = sim.get_t().min(t_max) - t.max(t_relax);
+        if dt > 0.0 {
+            moments.rho += rho * dt;
+            moments.rho2 += rho * rho * dt;
+            moments.rho4 += rho.powi(4) * dt;
+            moments.time += dt;
+        }
+    }
+    moments
+}
+
+pub struct FssPoint {
+    pub l: usize,
+    pub lambda: f64,
+    pub order_parameter: f64,
+    pub susceptibility: f64,
+    pub moment_ratio: f64,
+    pub binder_cumulant: f64,
+    pub collapse_residual: f64,
+}
+
+impl FssPoint {
+    pub fn new(l: usize, dimensions: usize, lambda: f64, moments: &Moments) -> Self {
+        let rho = moments.rho / moments.time;
+        let rho2 = moments.rho2 / moments.time;
+        let rho4 = moments.rho4 / moments.time;
+        FssPoint {
+            l,
+            lambda,
+            order_parameter: rho,
+            susceptibility: (l as f64).powi(dimensions as i32) * (rho2 - rho * rho),
+            moment_ratio: rho2 / (rho * rho),
+            binder_cumulant: 1.0 - rho4 / (3.0 * rho2 * rho2),
+            collapse_residual: f64::NAN,
+        }
+    }
+}
+
+pub struct FssFit {
+    pub lambda_c_crossing: f64,
+    pub lambda_c: f64,
+    pub beta_over_nu: f64,
+    pub nu_perp: f64,
+    pub residual: f64,
+}
+
+/// Directed percolation values of (beta / nu_perp, nu_perp) used as starting point of the fit.
+fn dp_exponents(dimensions: usize) -> (f64, f64) {
+    if dimensions == 1 {
+        (0.2521, 1.0969)
+    } else {
+        (0.795, 0.733)
+    }
+}
+
+fn sizes(points: &[FssPoint]) -> Vec<usize> {
+    let mut sizes: Vec<usize> = points.iter().map(|p| p.l).collect();
+    sizes.sort();
+    sizes.dedup();
+    sizes
+}
+
+fn curve(points: &[FssPoint], l: usize) -> Vec<&FssPoint> {
+    let mut curve: Vec<&FssPoint> = points.iter()
+        .filter(|p| p.l == l && p.order_parameter.is_finite())
+        .collect();
+    curve.sort_by(|a, b| a.lambda.partial_cmp(&b.lambda).unwrap());
+    curve
+}
+
+/// Lambda where the moment ratios of consecutive system sizes cross,
+/// found by linear interpolation on the lambda grid.
+pub fn crossings(points: &[FssPoint]) -> Vec<(usize, usize, f64)> {
+    let sizes = sizes(points);
+    let mut crossings = Vec::new();
+    for pair in sizes.windows(2) {
+        let small = curve(points, pair[0]);
+        let large = curve(points, pair[1]);
+        let differences: Vec<(f64, f64)> = small.iter()
+            .filter_map(|s| large.iter()
+                .find(|l| l.lambda == s.lambda)
+                .map(|l| (s.lambda, l.moment_ratio - s.moment_ratio)))
+            .collect();
+        for window in differences.windows(2) {
+            let ((x0, d0), (x1, d1)) = (window[0], window[1]);
+            if d0 == 0.0 || d0.signum() != d1.signum() {
+                crossings.push((pair[0], pair[1], x0 - d0 * (x1 - x0) / (d1 - d0)));
+                break;
+            }
+        }
+    }
+    crossings
+}
+
+/// Scaled coordinates `((lambda - lambda_c) L^(1/nu), rho L^(beta/nu))` of one curve.
+fn scaled_curve(curve: &[&FssPoint], lambda_c: f64, beta_over_nu: f64, nu_perp: f64) -> Vec<(f64, f64)> {
+    curve.iter().map(|p| {
+        let l = p.l as f64;
+        ((p.lambda - lambda_c) * l.powf(1.0 / nu_perp), p.order_parameter * l.powf(beta_over_nu))
+    }).collect()
+}
+
+fn interpolate(curve: &[(f64, f64)], x: f64) -> Option<f64> {
+    curve.windows(2)
+        .find(|w| w[0].0 <= x && x <= w[1].0)
+        .map(|w| {
+            let ((x0, y0), (x1, y1)) = (w[0], w[1]);
+            if x1 == x0 { y0 } else { y0 + (y1 - y0) * (x - x0) / (x1 - x0) }
+        })
+}
+
+/// Relative squared distance of every point to the other scaled curves at the same x,
+/// returned in the order of `points`. Points without an overlapping curve get NaN.
+pub fn collapse_residuals(points: &[FssPoint], lambda_c: f64, beta_over_nu: f64, nu_perp: f64) -> Vec<f64> {
+    let sizes = sizes(points);
+    let curves: Vec<(usize, Vec<(f64, f64)>)> = sizes.iter()
+        .map(|&l| (l, scaled_curve(&curve(points, l), lambda_c, beta_over_nu, nu_perp)))
+        .collect();
+
+    points.iter().map(|p| {
+        let l = p.l as f64;
+        let x = (p.lambda - lambda_c) * l.powf(1.0 / nu_perp);
+        let y = p.order_parameter * l.powf(beta_over_nu);
+        let distances: Vec<f64> = curves.iter()
+            .filter(|(other, _)| *other != p.l)
+            .filter_map(|(_, c)| interpolate(c, x))
+            .map(|other| ((y - other) / other).powi(2))
+            .collect();
+        if distances.is_empty() {
+            f64::NAN
+        } else {
+            distances.iter().sum::<f64>() / distances.len() as f64
+        }
+    }).collect()
+}
+
+fn mean_residual(residuals: &[f64]) -> f64 {
+    let finite: Vec<f64> = residuals.iter().copied().filter(|r| r.is_finite()).collect();
+    if finite.is_empty() {
+        return f64::INFINITY;
+    }
+    finite.iter().sum::<f64>() / finite.len() as f64
+}
+
+/// Estimates lambda_c from the crossings of the moment ratio and then fits lambda_c,
+/// beta / nu_perp and nu_perp by minimizing the collapse residual of the order parameter.
+/// Fills in `collapse_residual` of every point for the fitted parameters.
+pub fn analyse(points: &mut [FssPoint], dimensions: usize) -> FssFit {
+    let crossings = crossings(points);
+    let lambda_c_crossing = if crossings.is_empty() {
+        let lambdas: Vec<f64> = points.iter().map(|p| p.lambda).collect();
+        lambdas.iter().sum::<f64>() / lambdas.len() as f64
+    } else {
+        crossings.iter().map(|c| c.2).sum::<f64>() / crossings.len() as f64
+    };
+
+    let (beta_over_nu, nu_perp) = dp_exponents(dimensions);
+    let objective = |p: &[f64]| {
+        if p[2] <= 0.0 {
+            return f64::INFINITY;
+        }
+        mean_residual(&collapse_residuals(points, p[0], p[1], p[2]))
+    };
+    let (best, residual) = nelder_mead(objective,
+                                       &[lambda_c_crossing, beta_over_nu, nu_perp],
+                                       &[0.01, 0.05, 0.1],
+                                       COLLAPSE_ITERATIONS);
+
+    let residuals = collapse_residuals(points, best[0], best[1], best[2]);
+    for (point, residual) in points.iter_mut().zip(residuals) {
+        point.collapse_residual = residual;
+    }
+
+    FssFit {
+        lambda_c_crossing,
+        lambda_c: best[0],
+        beta_over_nu: best[1],
+        nu_perp: best[2],
+        residual,
+    }
+}
@@ -0,0 +1,10 @@
+pub mod fit;
+pub mod sampling;
+pub mod front;
+pub mod shape;
+pub mod outbreak;
+pub mod extinction;
+pub mod stats;
+pub mod sums;
+pub mod fss;
+pub mod spreading;
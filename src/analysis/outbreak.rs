@@ -0,0 +1,73 @@
+/// Outbreak statistics averaged over all runs of one grid point, see `RunSums::outbreak`.
+#[derive(Clone, Copy, Default)]
+pub struct OutbreakAverage {
+    pub ever_infected: f64,
+    pub infections: f64,
+    pub peak: f64,
+    pub peak_t: f64,
+}
+
+impl OutbreakAverage {
+    pub fn csv_header() -> [&'static str; 4] {
+        ["Ever Infected Average", "Infections Average", "Peak Average", "Peak Time Average"]
+    }
+
+    pub fn csv_record(&self) -> [String; 4] {
+        [
+            self.ever_infected.to_string(),
+            self.infections.to_string(),
+            self.peak.to_string(),
+            self.peak_t.to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::analysis::sums::RunSums;
+    use crate::simulation::registry::test_models;
+    use crate::simulation::sim::OutbreakStats;
+
+    #[test]
+    fn test_average() {
+        let mut sums = RunSums::default();
+        sums.add(true, 0, OutbreakStats { ever_infected: 1, infections: 1, peak: 1, peak_t: 0.0 });
+        sums.add(false, 4, OutbreakStats { ever_infected: 10, infections: 14, peak: 6, peak_t: 3.0 });
+        sums.add(true, 0, OutbreakStats { ever_infected: 4, infections: 4, peak: 2, peak_t: 1.5 });
+        let average = sums.outbreak();
+        assert_eq!(average.ever_infected, 5.0);
+        assert_eq!(average.infections, 19.0 / 3.0);
+        assert_eq!(average.peak, 3.0);
+        assert_eq!(average.peak_t, 1.5);
+        assert_eq!(average.csv_record(), ["5", &(19.0_f64 / 3.0).to_string(), "3", "1.5"]);
+        assert_eq!(RunSums::default().outbreak().peak, 0.0);
+    }
+
+    //the statistics every engine keeps agree with the ones recounted from its infected sites after every event
+    #[test]
+    fn test_engine_stats() {
+        for (model, values) in test_models(2.5, 0.7) {
+            let mut sim = model.create_with_seed(&values, 5).unwrap();
+            let mut infected: HashSet<(i32, i32)> = sim.get_infected_sites().into_iter().collect();
+            let mut ever_infected = infected.clone();
+            let (mut infections, mut peak, mut peak_t) = (1, 1, 0.0);
+            while sim.get_t() < 8.0 && sim.step() {
+                let now: HashSet<(i32, i32)> = sim.get_infected_sites().into_iter().collect();
+                infections += now.difference(&infected).count();
+                ever_infected.extend(&now);
+                if now.len() > peak {
+                    peak = now.len();
+                    peak_t = sim.get_t();
+                }
+                infected = now;
+            }
+            let stats = sim.get_outbreak_stats();
+            assert_eq!(stats.ever_infected, ever_infected.len(), "{}", model.name);
+            assert_eq!(stats.infections, infections, "{}", model.name);
+            assert_eq!((stats.peak, stats.peak_t), (peak, peak_t), "{}", model.name);
+            assert!(infections > 20, "{} died out too early to test anything", model.name);
+        }
+    }
+}
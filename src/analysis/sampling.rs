@@ -0,0 +1,71 @@
+use crate::simulation::sim::Sim;
+
+/// Runs `sim` until `t_max` and records `observe(sim)` at the times `0, dt, 2 dt, ...`.
+/// The value recorded for a sample time is the state after the last event before it,
+/// samples after the disease died out keep the (empty) final state.
+/// `S` is a concrete simulation when `observe` needs more than the `Sim` trait.
+pub fn sample<S: Sim + ?Sized, T: Clone, F: Fn(&S) -> T>(sim: &mut S, t_max: f64, dt: f64, observe: F) -> Vec<(f64, T)> {
+    //with dt = 0 the sample times would never pass t_max
+    assert!(dt > 0.0 && dt.is_finite(), "sampling interval dt = {} is not positive", dt);
+    let times: Vec<f64> = (0..).map(|k| k as f64 * dt).take_while(|&t| t <= t_max).collect();
+    times.iter().copied().zip(sample_at(sim, &times, observe)).collect()
+}
+
+/// Like `sample` but at arbitrary increasing `times`.
+/// Steps until the next event would pass the next sample time, so `observe` runs once per
+/// state that is actually sampled instead of after every event.
+pub fn sample_at<S: Sim + ?Sized, T: Clone, F: Fn(&S) -> T>(sim: &mut S, times: &[f64], observe: F) -> Vec<T> {
+    let mut samples = Vec::with_capacity(times.len());
+    while samples.len() < times.len() {
+        //the current state holds until the next event, infinitely long once the disease died
+        let next_t = sim.get_next_t();
+        if times[samples.len()] < next_t {
+            let current = observe(sim);
+            while samples.len() < times.len() && times[samples.len()] < next_t {
+                samples.push(current.clone());
+            }
+        } else {
+            sim.step();
+        }
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+    use crate::simulation::sim1d;
+
+    #[test]
+    fn test_sample_at_matches_every_event() {
+        let times: Vec<f64> = (0..40).map(|k| k as f64 * 0.5).collect();
+        let params = || sim1d::ModelParams::new(2.0).unwrap();
+
+        //reference: the state after every event, kept until the event that passes a sample time
+        let mut sim = sim1d::Simulation::with_seed(params(), 7);
+        let mut expected = Vec::new();
+        let mut current = sim.get_number_of_infected_nodes();
+        loop {
+            let alive = sim.step();
+            let t = if alive { sim.get_t() } else { f64::INFINITY };
+            while expected.len() < times.len() && times[expected.len()] < t {
+                expected.push(current);
+            }
+            if !alive || expected.len() == times.len() {
+                break;
+            }
+            current = sim.get_number_of_infected_nodes();
+        }
+
+        let calls = Cell::new(0);
+        let mut sim = sim1d::Simulation::with_seed(params(), 7);
+        let samples = sample_at(&mut sim, &times, |sim| {
+            calls.set(calls.get() + 1);
+            sim.get_number_of_infected_nodes()
+        });
+        assert_eq!(samples, expected);
+        assert!(calls.get() <= times.len());
+    }
+}
@@ -0,0 +1,100 @@
+use std::f64::consts::PI;
+
+use serde::Deserialize;
+
+use crate::simulation::sim::Sim;
+use crate::simulation::sim2d;
+
+use super::fit::mean_std_err;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Region {
+    Infected,
+    EverInfected,
+}
+
+pub struct ShapePoint {
+    pub angle: f64,
+    pub radius: f64,
+    pub radius_std_err: f64,
+}
+
+pub struct ShapeEstimate {
+    pub surviving: usize,
+    pub points: Vec<ShapePoint>,
+}
+
+/// Splits the plane into `n_angles` sectors around the origin and returns the
+/// largest distance of a site from the origin in every sector.
+/// Sector `k` is centred at the angle `2 pi k / n_angles`.
+pub fn shape_profile<I: Iterator<Item = (i32, i32)>>(sites: I, n_angles: usize) -> Vec<f64> {
+    let mut radii = vec![0.0; n_angles];
+    let width = 2.0 * PI / n_angles as f64;
+    for (x, y) in sites {
+        let (x, y) = (x as f64, y as f64);
+        let angle = y.atan2(x).rem_euclid(2.0 * PI);
+        let sector = ((angle / width).round() as usize) % n_angles;
+        let r = (x * x + y * y).sqrt();
+        if r > radii[sector] {
+            radii[sector] = r;
+        }
+    }
+    radii
+}
+
+/// Runs `sim2d` once per seed until t_max and averages the profile of `region` divided by t_max
+/// over all runs that survive, which approximates the asymptotic shape.
+pub fn estimate_shape<I: IntoIterator<Item = u64>>(params: sim2d::ModelParams,
+                                                    seeds: I,
+                                                    t_max: f64,
+                                                    n_angles: usize,
+                                                    region: Region) -> ShapeEstimate {
+    let mut profiles = Vec::new();
+    for seed in seeds {
+        let mut sim = sim2d::Simulation::with_seed(params, seed);
+        if sim.run(t_max) {
+            continue;
+        }
+        let profile = match region {
+            Region::Infected => shape_profile(sim.get_infected_sites().into_iter(), n_angles),
+            Region::EverInfected => shape_profile(sim.get_ever_infected_sites(), n_angles),
+        };
+        profiles.push(profile);
+    }
+
+    let points = (0..n_angles).map(|k| {
+        let radii: Vec<f64> = profiles.iter().map(|p| p[k] / t_max).collect();
+        let (radius, radius_std_err) = mean_std_err(&radii).unwrap_or((f64::NAN, f64::NAN));
+        ShapePoint {
+            angle: 2.0 * PI * k as f64 / n_angles as f64,
+            radius,
+            radius_std_err,
+        }
+    }).collect();
+
+    ShapeEstimate {
+        surviving: profiles.len(),
+        points,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shape_profile() {
+        //the diamond |x| + |y| <= 3
+        let diamond = (-3..=3).flat_map(|x: i32| (-3..=3).map(move |y: i32| (x, y))).filter(|(x, y)| x.abs() + y.abs() <= 3);
+        let profile = shape_profile(diamond, 8);
+        let axis = 3.0;
+        //(1, 2) and (2, 1) are the farthest sites of the diagonal sectors
+        let diagonal = 5.0_f64.sqrt();
+        for (k, radius) in profile.iter().enumerate() {
+            let expected = if k % 2 == 0 { axis } else { diagonal };
+            assert!((radius - expected).abs() < 1e-12, "sector {} has radius {}", k, radius);
+        }
+        assert_eq!(shape_profile(std::iter::once((0, 0)), 4), vec![0.0; 4]);
+    }
+}
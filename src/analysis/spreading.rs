@@ -0,0 +1,200 @@
+use crate::simulation::sim::Sim;
+
+use super::fit::linear_fit;
+use super::sampling::sample_at;
+
+/// Sample times are spaced by a factor of 2^(1 / TIMES_PER_OCTAVE).
+pub const TIMES_PER_OCTAVE: usize = 4;
+
+/// Local slopes compare t with t / 2.
+const LOCAL_SLOPE_DISTANCE: usize = TIMES_PER_OCTAVE;
+
+/// Fraction of the sample times (on the log scale) used for the asymptotic fit.
+const FIT_FRACTION: f64 = 1.0 / 3.0;
+
+pub fn log_times(t_min: f64, t_max: f64) -> Vec<f64> {
+    let factor = 2f64.powf(1.0 / TIMES_PER_OCTAVE as f64);
+    (0..).map(|k| t_min * factor.powi(k)).take_while(|&t| t <= t_max).collect()
+}
+
+/// Splits `runs` into `batches` batches whose sizes differ by at most one.
+pub fn batch_sizes(runs: usize, batches: usize) -> Vec<usize> {
+    (0..batches).map(|b| runs / batches + usize::from(b < runs % batches)).collect()
+}
+
+/// Sums over all runs of one batch at every sample time.
+#[derive(Clone)]
+pub struct SpreadingSums {
+    pub runs: usize,
+    pub surviving: Vec<usize>,
+    pub infected: Vec<f64>,
+    pub squared_distance: Vec<f64>,
+}
+
+impl SpreadingSums {
+    pub fn new(samples: usize) -> Self {
+        SpreadingSums {
+            runs: 0,
+            surviving: vec![0; samples],
+            infected: vec![0.0; samples],
+            squared_distance: vec![0.0; samples],
+        }
+    }
+
+    pub fn add_run(&mut self, sim: &mut dyn Sim, times: &[f64]) {
+        let samples = sample_at(sim, times, |sim| {
+            (sim.get_number_of_infected_nodes(), sim.get_squared_distance_sum())
+        });
+        self.runs += 1;
+        for (k, (infected, squared_distance)) in samples.into_iter().enumerate() {
+            if infected > 0 {
+                self.surviving[k] += 1;
+            }
+            self.infected[k] += infected as f64;
+            self.squared_distance[k] += squared_distance;
+        }
+    }
+
+    pub fn merge(&mut self, other: &SpreadingSums) {
+        self.runs += other.runs;
+        for k in 0..self.surviving.len() {
+            self.surviving[k] += other.surviving[k];
+            self.infected[k] += other.infected[k];
+            self.squared_distance[k] += other.squared_distance[k];
+        }
+    }
+
+    /// P(t), n(t) and R^2(t) at sample `k`. n(t) is averaged over all runs and
+    /// R^2(t) is the mean squared distance of an infected site from the origin.
+    fn observables(&self, k: usize) -> [f64; 3] {
+        let runs = self.runs as f64;
+        [
+            self.surviving[k] as f64 / runs,
+            self.infected[k] / runs,
+            self.squared_distance[k] / self.infected[k],
+        ]
+    }
+}
+
+/// Observables and effective exponents `delta(t)`, `eta(t)`, `z(t)` at one sample time,
+/// defined by P(t) ~ t^-delta, n(t) ~ t^eta and R^2(t) ~ t^z. Exponents carry their error.
+pub struct SpreadingPoint {
+    pub t: f64,
+    pub survival: f64,
+    pub infected: f64,
+    pub squared_radius: f64,
+    pub exponents: [(f64, f64); 3],
+}
+
+/// Exponents from a fit over the last part of the time range.
+pub struct SpreadingFit {
+    pub delta: (f64, f64),
+    pub eta: (f64, f64),
+    pub z: (f64, f64),
+}
+
+fn local_slopes(sums: &SpreadingSums, times: &[f64], k: usize) -> [f64; 3] {
+    let j = k - LOCAL_SLOPE_DISTANCE;
+    let now = sums.observables(k);
+    let before = sums.observables(j);
+    let log_t = (times[k] / times[j]).ln();
+    [0, 1, 2].map(|i| (now[i] / before[i]).ln() / log_t)
+}
+
+fn fitted_slopes(sums: &SpreadingSums, times: &[f64]) -> [f64; 3] {
+    let first = ((1.0 - FIT_FRACTION) * times.len() as f64) as usize;
+    let log_t: Vec<f64> = times[first..].iter().map(|t| t.ln()).collect();
+    [0, 1, 2].map(|i| {
+        let log_q: Vec<f64> = (first..times.len()).map(|k| sums.observables(k)[i].ln()).collect();
+        let valid: Vec<(f64, f64)> = log_t.iter().copied().zip(log_q).filter(|(_, q)| q.is_finite()).collect();
+        let (xs, ys): (Vec<f64>, Vec<f64>) = valid.into_iter().unzip();
+        linear_fit(&xs, &ys).map(|fit| fit.slope).unwrap_or(f64::NAN)
+    })
+}
+
+/// Jackknife estimate and error, leaving out one batch at a time.
+fn jackknife<F: Fn(&SpreadingSums) -> [f64; 3]>(batches: &[SpreadingSums], total: &SpreadingSums, estimate: F) -> [(f64, f64); 3] {
+    let full = estimate(total);
+    let b = batches.len();
+    if b < 2 {
+        return full.map(|v| (v, f64::NAN));
+    }
+    let leave_one_out: Vec<[f64; 3]> = (0..b).map(|left_out| {
+        let mut sums = SpreadingSums::new(total.surviving.len());
+        for (i, batch) in batches.iter().enumerate() {
+            if i != left_out {
+                sums.merge(batch);
+            }
+        }
+        estimate(&sums)
+    }).collect();
+    [0, 1, 2].map(|i| {
+        let mean = leave_one_out.iter().map(|e| e[i]).sum::<f64>() / b as f64;
+        let variance = leave_one_out.iter().map(|e| (e[i] - mean).powi(2)).sum::<f64>();
+        (full[i], ((b - 1) as f64 / b as f64 * variance).sqrt())
+    })
+}
+
+/// Combines independent batches of runs at the sample `times` into the observables
+/// with local slopes and the asymptotic fit. The errors are jackknife errors over the batches.
+/// Since P(t) ~ t^-delta the returned delta has the sign flipped.
+pub fn analyse(batches: &[SpreadingSums], times: &[f64]) -> (Vec<SpreadingPoint>, SpreadingFit) {
+    let mut total = SpreadingSums::new(times.len());
+    for batch in batches {
+        total.merge(batch);
+    }
+
+    let points = (0..times.len()).map(|k| {
+        let [survival, infected, squared_radius] = total.observables(k);
+        let exponents = if k >= LOCAL_SLOPE_DISTANCE {
+            let mut e = jackknife(batches, &total, |sums| local_slopes(sums, times, k));
+            e[0].0 = -e[0].0;
+            e
+        } else {
+            [(f64::NAN, f64::NAN); 3]
+        };
+        SpreadingPoint { t: times[k], survival, infected, squared_radius, exponents }
+    }).collect();
+
+    let [delta, eta, z] = jackknife(batches, &total, |sums| fitted_slopes(sums, times));
+    let fit = SpreadingFit { delta: (-delta.0, delta.1), eta, z };
+    (points, fit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_sizes() {
+        assert_eq!(batch_sizes(45, 20).iter().sum::<usize>(), 45);
+        assert_eq!(batch_sizes(45, 20)[..6], [3, 3, 3, 3, 3, 2]);
+        assert_eq!(batch_sizes(40, 20), vec![2; 20]);
+    }
+
+    #[test]
+    fn test_analyse() {
+        //P(t) = t^-0.5, n(t) = t^0.25 and R^2(t) = t^1.2 in every batch
+        let times = log_times(1.0, 1024.0);
+        let runs = 1_000_000;
+        let mut batch = SpreadingSums::new(times.len());
+        batch.runs = runs;
+        for (k, t) in times.iter().enumerate() {
+            batch.surviving[k] = (runs as f64 * t.powf(-0.5)).round() as usize;
+            batch.infected[k] = runs as f64 * t.powf(0.25);
+            batch.squared_distance[k] = batch.infected[k] * t.powf(1.2);
+        }
+        let (points, fit) = analyse(&[batch.clone(), batch.clone(), batch], &times);
+        assert_eq!(points.len(), times.len());
+        assert!(points[0].exponents[0].0.is_nan());
+        let last = &points[times.len() - 1];
+        for (exponent, expected) in last.exponents.iter().zip([0.5, 0.25, 1.2]) {
+            assert!((exponent.0 - expected).abs() < 1e-3, "{} instead of {}", exponent.0, expected);
+        }
+        for (exponent, expected) in [fit.delta, fit.eta, fit.z].iter().zip([0.5, 0.25, 1.2]) {
+            assert!((exponent.0 - expected).abs() < 1e-3, "{} instead of {}", exponent.0, expected);
+            //identical batches leave no spread for the jackknife
+            assert!(exponent.1 < 1e-9);
+        }
+    }
+}
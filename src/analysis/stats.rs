@@ -0,0 +1,371 @@
+/// Confidence level of all intervals written to the result files.
+pub const CONFIDENCE: f64 = 0.95;
+
+/// Natural logarithm of the gamma function (Lanczos approximation).
+pub fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.18009172947146,
+        -86.50532032941677,
+        24.01409824083091,
+        -1.231739572450155,
+        0.1208650973866179e-2,
+        -0.5395239384953e-5,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let mut series = 1.000000000190015;
+    for (i, c) in COEFFICIENTS.iter().enumerate() {
+        series += c / (x + 1.0 + i as f64);
+    }
+    -tmp + (2.5066282746310005 * series / x).ln()
+}
+
+/// Regularized incomplete beta function I_x(a, b).
+pub fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front = (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 500;
+    const EPS: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+    let mut c = 1.0;
+    let mut d = 1.0 - (a + b) * x / (a + 1.0);
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 + even * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + even / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        h *= d * c;
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 + odd * d;
+        d = if d.abs() < TINY { 1.0 / TINY } else { 1.0 / d };
+        c = 1.0 + odd / c;
+        if c.abs() < TINY {
+            c = TINY;
+        }
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < EPS {
+            break;
+        }
+    }
+    h
+}
+
+/// Inverse of the regularized incomplete beta function in x, found by bisection.
+pub fn beta_quantile(a: f64, b: f64, p: f64) -> f64 {
+    let (mut lower, mut upper) = (0.0, 1.0);
+    for _ in 0..100 {
+        let mid = (lower + upper) / 2.0;
+        if incomplete_beta(a, b, mid) < p {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    (lower + upper) / 2.0
+}
+
+/// Quantile of the standard normal distribution (Acklam's rational approximation).
+pub fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [-3.969683028665376e1, 2.209460984245205e2, -2.759285104469687e2,
+        1.38357751867269e2, -3.066479806614716e1, 2.506628277459239];
+    const B: [f64; 5] = [-5.447609879822406e1, 1.615858368580409e2, -1.556989798598866e2,
+        6.680131188771972e1, -1.328068155288572e1];
+    const C: [f64; 6] = [-7.784894002430293e-3, -3.223964580411365e-1, -2.400758277161838,
+        -2.549732539343734, 4.374664141464968, 2.938163982698783];
+    const D: [f64; 4] = [7.784695709041462e-3, 3.224671290700398e-1, 2.445134137142996,
+        3.754408661907416];
+    const P_LOW: f64 = 0.02425;
+
+    if p < P_LOW {
+        let q = (-2.0 * p.ln()).sqrt();
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.0)
+    } else if p <= 1.0 - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.0)
+    } else {
+        -normal_quantile(1.0 - p)
+    }
+}
+
+/// Wilson score interval for `successes` out of `n` Bernoulli trials.
+pub fn wilson_interval(successes: usize, n: usize, confidence: f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let z = normal_quantile(1.0 - (1.0 - confidence) / 2.0);
+    let n = n as f64;
+    let p = successes as f64 / n;
+    let denominator = 1.0 + z * z / n;
+    let centre = (p + z * z / (2.0 * n)) / denominator;
+    let half_width = z * (p * (1.0 - p) / n + z * z / (4.0 * n * n)).sqrt() / denominator;
+    ((centre - half_width).max(0.0), (centre + half_width).min(1.0))
+}
+
+/// Exact Clopper-Pearson interval for `successes` out of `n` Bernoulli trials.
+pub fn clopper_pearson_interval(successes: usize, n: usize, confidence: f64) -> (f64, f64) {
+    if n == 0 {
+        return (0.0, 1.0);
+    }
+    let alpha = 1.0 - confidence;
+    let k = successes as f64;
+    let n = n as f64;
+    let lower = if successes == 0 { 0.0 } else { beta_quantile(k, n - k + 1.0, alpha / 2.0) };
+    let upper = if successes as f64 == n { 1.0 } else { beta_quantile(k + 1.0, n - k, 1.0 - alpha / 2.0) };
+    (lower, upper)
+}
+
+/// Regularized upper incomplete gamma function Q(a, x), by its series for small x
+/// and by a continued fraction otherwise.
+#[cfg(test)]
+pub fn incomplete_gamma_q(a: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 500;
+    const EPS: f64 = 1e-14;
+    const TINY: f64 = 1e-300;
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let front = (-x + a * x.ln() - ln_gamma(a)).exp();
+    if x < a + 1.0 {
+        let mut term = 1.0 / a;
+        let mut sum = term;
+        for n in 1..=MAX_ITERATIONS {
+            term *= x / (a + n as f64);
+            sum += term;
+            if term.abs() < sum.abs() * EPS {
+                break;
+            }
+        }
+        1.0 - front * sum
+    } else {
+        let mut b = x + 1.0 - a;
+        let mut c = 1.0 / TINY;
+        let mut d = 1.0 / b;
+        let mut h = d;
+        for i in 1..=MAX_ITERATIONS {
+            let an = -(i as f64) * (i as f64 - a);
+            b += 2.0;
+            d = an * d + b;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = b + an / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            let delta = d * c;
+            h *= delta;
+            if (delta - 1.0).abs() < EPS {
+                break;
+            }
+        }
+        front * h
+    }
+}
+
+/// Chi-square test that two binned samples come from the same distribution.
+/// The samples may have different sizes, bins empty in both are skipped.
+/// Returns the statistic and its p-value, which is 1 when fewer than two bins are occupied.
+#[cfg(test)]
+pub fn chi_square_two_sample(a: &[usize], b: &[usize]) -> (f64, f64) {
+    let total_a = a.iter().sum::<usize>() as f64;
+    let total_b = b.iter().sum::<usize>() as f64;
+    let (scale_a, scale_b) = ((total_b / total_a).sqrt(), (total_a / total_b).sqrt());
+    let mut statistic = 0.0;
+    let mut bins = 0;
+    for (&x, &y) in a.iter().zip(b) {
+        if x + y == 0 {
+            continue;
+        }
+        bins += 1;
+        let difference = scale_a * x as f64 - scale_b * y as f64;
+        statistic += difference * difference / (x + y) as f64;
+    }
+    if bins < 2 {
+        return (0.0, 1.0);
+    }
+    let degrees_of_freedom = (bins - 1) as f64;
+    (statistic, incomplete_gamma_q(degrees_of_freedom / 2.0, statistic / 2.0))
+}
+
+/// Survival function of the Kolmogorov distribution.
+#[cfg(test)]
+fn kolmogorov_q(lambda: f64) -> f64 {
+    if lambda < 1e-3 {
+        return 1.0;
+    }
+    let mut sum = 0.0;
+    let mut sign = 1.0;
+    for k in 1..=100 {
+        let term = sign * (-2.0 * (k * k) as f64 * lambda * lambda).exp();
+        sum += term;
+        if term.abs() < 1e-12 {
+            break;
+        }
+        sign = -sign;
+    }
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+/// Two sample Kolmogorov-Smirnov test, returns the largest distance between the
+/// empirical distribution functions and its asymptotic p-value.
+#[cfg(test)]
+pub fn ks_two_sample(a: &[f64], b: &[f64]) -> (f64, f64) {
+    let mut a = a.to_vec();
+    let mut b = b.to_vec();
+    a.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    b.sort_by(|x, y| x.partial_cmp(y).unwrap());
+    let (n_a, n_b) = (a.len() as f64, b.len() as f64);
+    let (mut i, mut j) = (0, 0);
+    let mut distance: f64 = 0.0;
+    while i < a.len() && j < b.len() {
+        let x = a[i].min(b[j]);
+        // ties are stepped over together so equal values never count as a distance
+        while i < a.len() && a[i] == x {
+            i += 1;
+        }
+        while j < b.len() && b[j] == x {
+            j += 1;
+        }
+        distance = distance.max((i as f64 / n_a - j as f64 / n_b).abs());
+    }
+    let effective = (n_a * n_b / (n_a + n_b)).sqrt();
+    (distance, kolmogorov_q((effective + 0.12 + 0.11 / effective) * distance))
+}
+
+/// Uncertainty of the averages of one grid point.
+#[derive(Clone, Copy, Default)]
+pub struct Uncertainty {
+    pub runs: usize,
+    pub died: usize,
+    pub end_nodes_variance: f64,
+}
+
+impl Uncertainty {
+    /// `end_nodes_sum` and `end_nodes_sq_sum` are the sum of the final number of
+    /// infected nodes and of its square over all runs.
+    pub fn new(runs: usize, died: usize, end_nodes_sum: f64, end_nodes_sq_sum: f64) -> Self {
+        let end_nodes_variance = if runs > 1 {
+            let n = runs as f64;
+            ((end_nodes_sq_sum - end_nodes_sum * end_nodes_sum / n) / (n - 1.0)).max(0.0)
+        } else {
+            0.0
+        };
+        Uncertainty { runs, died, end_nodes_variance }
+    }
+
+    pub fn wilson(&self) -> (f64, f64) {
+        wilson_interval(self.died, self.runs, CONFIDENCE)
+    }
+
+    pub fn clopper_pearson(&self) -> (f64, f64) {
+        clopper_pearson_interval(self.died, self.runs, CONFIDENCE)
+    }
+
+    pub fn end_nodes_std_err(&self) -> f64 {
+        if self.runs == 0 {
+            return 0.0;
+        }
+        (self.end_nodes_variance / self.runs as f64).sqrt()
+    }
+
+    pub fn csv_header() -> [&'static str; 7] {
+        ["Runs", "Died Wilson Lower", "Died Wilson Upper", "Died Clopper Pearson Lower",
+            "Died Clopper Pearson Upper", "End Nodes Variance", "End Nodes Std Err"]
+    }
+
+    pub fn csv_record(&self) -> [String; 7] {
+        let wilson = self.wilson();
+        let clopper_pearson = self.clopper_pearson();
+        [
+            self.runs.to_string(),
+            wilson.0.to_string(),
+            wilson.1.to_string(),
+            clopper_pearson.0.to_string(),
+            clopper_pearson.1.to_string(),
+            self.end_nodes_variance.to_string(),
+            self.end_nodes_std_err().to_string(),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normal_quantile() {
+        assert!((normal_quantile(0.975) - 1.959964).abs() < 1e-6);
+        assert!((normal_quantile(0.5)).abs() < 1e-9);
+        assert!((normal_quantile(0.001) + 3.090232).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_incomplete_beta() {
+        // I_x(1, 1) = x and I_x(2, 1) = x^2
+        assert!((incomplete_beta(1.0, 1.0, 0.3) - 0.3).abs() < 1e-10);
+        assert!((incomplete_beta(2.0, 1.0, 0.3) - 0.09).abs() < 1e-10);
+        assert!((beta_quantile(2.0, 1.0, 0.25) - 0.5).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_intervals() {
+        // reference values from R: binom.test(7, 20) and prop.test(7, 20, correct = FALSE)
+        let (lower, upper) = clopper_pearson_interval(7, 20, 0.95);
+        assert!((lower - 0.1539092).abs() < 1e-6);
+        assert!((upper - 0.5921885).abs() < 1e-6);
+        let (lower, upper) = wilson_interval(7, 20, 0.95);
+        assert!((lower - 0.1811918).abs() < 1e-6);
+        assert!((upper - 0.5671457).abs() < 1e-6);
+
+        assert_eq!(clopper_pearson_interval(0, 10, 0.95).0, 0.0);
+        assert_eq!(clopper_pearson_interval(10, 10, 0.95).1, 1.0);
+    }
+
+    #[test]
+    fn test_tests() {
+        // Q(1, x) = e^-x, and R: pchisq(7.5, 5, lower.tail = FALSE)
+        assert!((incomplete_gamma_q(1.0, 0.5) - (-0.5f64).exp()).abs() < 1e-10);
+        assert!((incomplete_gamma_q(1.0, 3.0) - (-3f64).exp()).abs() < 1e-10);
+        assert!((incomplete_gamma_q(2.5, 3.75) - 0.1860298).abs() < 1e-6);
+
+        let (statistic, p) = chi_square_two_sample(&[10, 20, 30], &[20, 40, 60]);
+        assert!(statistic.abs() < 1e-12);
+        assert!((p - 1.0).abs() < 1e-12);
+        assert_eq!(chi_square_two_sample(&[], &[]), (0.0, 1.0));
+        assert_eq!(chi_square_two_sample(&[0, 4, 0], &[0, 9, 0]), (0.0, 1.0));
+
+        let (distance, p) = ks_two_sample(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]);
+        assert_eq!(distance, 0.0);
+        assert_eq!(p, 1.0);
+        let (distance, _) = ks_two_sample(&[1.0, 2.0, 3.0, 4.0], &[3.5, 5.0]);
+        assert_eq!(distance, 0.75);
+        assert!((kolmogorov_q(1.0) - 0.2699996).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,151 @@
+use serde::{Deserialize, Serialize};
+
+use crate::simulation::sim::OutbreakStats;
+use super::outbreak::OutbreakAverage;
+use super::stats::Uncertainty;
+
+/// Counts and sums over the runs of one grid point. Every average and interval in the result
+/// files is derived from these, so result files of separate sweeps can be merged exactly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunSums {
+    pub runs: usize,
+    pub died: usize,
+    pub end_nodes_sum: f64,
+    pub end_nodes_sq_sum: f64,
+    pub ever_infected_sum: f64,
+    pub infections_sum: f64,
+    pub peak_sum: f64,
+    pub peak_t_sum: f64,
+}
+
+impl RunSums {
+    /// `died` is the result of `Sim::run`, `end_nodes` the number of infected nodes at its end.
+    pub fn add(&mut self, died: bool, end_nodes: usize, outbreak: OutbreakStats) {
+        self.runs += 1;
+        if died {
+            self.died += 1;
+        }
+        self.end_nodes_sum += end_nodes as f64;
+        self.end_nodes_sq_sum += (end_nodes as f64).powi(2);
+        self.ever_infected_sum += outbreak.ever_infected as f64;
+        self.infections_sum += outbreak.infections as f64;
+        self.peak_sum += outbreak.peak as f64;
+        self.peak_t_sum += outbreak.peak_t;
+    }
+
+    pub fn merge(&mut self, other: &RunSums) {
+        self.runs += other.runs;
+        self.died += other.died;
+        self.end_nodes_sum += other.end_nodes_sum;
+        self.end_nodes_sq_sum += other.end_nodes_sq_sum;
+        self.ever_infected_sum += other.ever_infected_sum;
+        self.infections_sum += other.infections_sum;
+        self.peak_sum += other.peak_sum;
+        self.peak_t_sum += other.peak_t_sum;
+    }
+
+    pub fn success_average(&self) -> f64 {
+        self.died as f64 / self.runs as f64
+    }
+
+    pub fn end_nodes_average(&self) -> f64 {
+        self.end_nodes_sum / self.runs as f64
+    }
+
+    pub fn uncertainty(&self) -> Uncertainty {
+        Uncertainty::new(self.runs, self.died, self.end_nodes_sum, self.end_nodes_sq_sum)
+    }
+
+    pub fn outbreak(&self) -> OutbreakAverage {
+        if self.runs == 0 {
+            return OutbreakAverage::default();
+        }
+        let n = self.runs as f64;
+        OutbreakAverage {
+            ever_infected: self.ever_infected_sum / n,
+            infections: self.infections_sum / n,
+            peak: self.peak_sum / n,
+            peak_t: self.peak_t_sum / n,
+        }
+    }
+
+    /// The number of runs is already part of `Uncertainty::csv_header`.
+    pub fn csv_header() -> [&'static str; 7] {
+        ["Died", "End Nodes Sum", "End Nodes Sq Sum", "Ever Infected Sum", "Infections Sum",
+            "Peak Sum", "Peak Time Sum"]
+    }
+
+    pub fn csv_record(&self) -> [String; 7] {
+        [
+            self.died.to_string(),
+            self.end_nodes_sum.to_string(),
+            self.end_nodes_sq_sum.to_string(),
+            self.ever_infected_sum.to_string(),
+            self.infections_sum.to_string(),
+            self.peak_sum.to_string(),
+            self.peak_t_sum.to_string(),
+        ]
+    }
+
+    /// Reads the sums back from a row of a result file, columns are found by their header.
+    pub fn from_csv(header: &csv::StringRecord, record: &csv::StringRecord) -> Result<Self, String> {
+        let column = |name: &str| -> Result<&str, String> {
+            let index = header.iter().position(|h| h == name)
+                .ok_or_else(|| format!("missing column {}", name))?;
+            record.get(index).ok_or_else(|| format!("row without column {}", name))
+        };
+        let float = |name: &str| -> Result<f64, String> {
+            column(name)?.parse().map_err(|_| format!("{} is not a number", name))
+        };
+        let count = |name: &str| -> Result<usize, String> {
+            column(name)?.parse().map_err(|_| format!("{} is not a count", name))
+        };
+        let header = Self::csv_header();
+        Ok(RunSums {
+            runs: count("Runs")?,
+            died: count(header[0])?,
+            end_nodes_sum: float(header[1])?,
+            end_nodes_sq_sum: float(header[2])?,
+            ever_infected_sum: float(header[3])?,
+            infections_sum: float(header[4])?,
+            peak_sum: float(header[5])?,
+            peak_t_sum: float(header[6])?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn outbreak(peak: usize, peak_t: f64) -> OutbreakStats {
+        OutbreakStats { ever_infected: peak + 1, infections: peak + 2, peak, peak_t }
+    }
+
+    #[test]
+    fn test_merge() {
+        let runs = [(true, 0, outbreak(3, 1.0)), (false, 5, outbreak(9, 4.0)), (true, 0, outbreak(1, 0.0)),
+            (false, 2, outbreak(4, 2.5)), (false, 7, outbreak(12, 8.0))];
+        let mut all = RunSums::default();
+        let mut first = RunSums::default();
+        let mut second = RunSums::default();
+        for (i, &(died, end_nodes, stats)) in runs.iter().enumerate() {
+            all.add(died, end_nodes, stats);
+            if i < 2 { first.add(died, end_nodes, stats) } else { second.add(died, end_nodes, stats) }
+        }
+        first.merge(&second);
+        assert_eq!(first, all);
+        assert_eq!(all.success_average(), 0.4);
+        assert_eq!(all.outbreak().peak, 29.0 / 5.0);
+        // sample variance of 0, 5, 0, 2, 7
+        assert!((all.uncertainty().end_nodes_variance - 9.7).abs() < 1e-12);
+
+        let mut header: Vec<&str> = vec!["Lambda", "Runs"];
+        header.extend(RunSums::csv_header());
+        let mut record = vec!["1.5".to_string(), all.runs.to_string()];
+        record.extend(all.csv_record());
+        let parsed = RunSums::from_csv(&csv::StringRecord::from(header), &csv::StringRecord::from(record)).unwrap();
+        assert_eq!(parsed, all);
+        assert!(RunSums::from_csv(&csv::StringRecord::from(vec!["Lambda"]), &csv::StringRecord::from(vec!["1.5"])).is_err());
+    }
+}
@@ -0,0 +1,224 @@
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// Accumulated replicas of one grid point.
+pub trait Replicas {
+    fn merge(&mut self, other: Self);
+}
+
+#[derive(Debug)]
+pub enum CheckpointError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    /// The file belongs to a sweep with a different model, t_max or seed, merging would mix results.
+    Mismatch(String),
+}
+
+impl Display for CheckpointError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckpointError::Io(error) => write!(f, "checkpoint, {}", error),
+            CheckpointError::Format(error) => write!(f, "corrupt checkpoint, {}", error),
+            CheckpointError::Mismatch(message) => write!(f, "checkpoint does not match the sweep, {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CheckpointError {}
+
+impl From<io::Error> for CheckpointError {
+    fn from(error: io::Error) -> Self {
+        CheckpointError::Io(error)
+    }
+}
+
+impl From<serde_json::Error> for CheckpointError {
+    fn from(error: serde_json::Error) -> Self {
+        CheckpointError::Format(error)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Contents<T> {
+    model: String,
+    t_max: f64,
+    seed: u64,
+    points: Vec<(Vec<f64>, Point<T>)>,
+}
+
+//batches finish in any order, so the replicas done are kept as sorted disjoint ranges
+#[derive(Serialize, Deserialize)]
+struct Point<T> {
+    done: Vec<(usize, usize)>,
+    tally: T,
+}
+
+/// Tallies of a sweep keyed by the parameter values of their grid point, together with the
+/// indices of the replicas in them. Every merged batch is written to disk right away, so a
+/// restarted sweep only runs the replicas still missing. Grid points outside the current range
+/// are kept in the file for later sweeps.
+pub struct Checkpoint<T> {
+    path: Option<PathBuf>,
+    contents: Contents<T>,
+}
+
+impl<T: Replicas + Serialize + DeserializeOwned> Checkpoint<T> {
+    /// A checkpoint which is never written, for sweeps that are not resumable.
+    pub fn in_memory(model: &str, t_max: f64, seed: u64) -> Self {
+        Checkpoint {
+            path: None,
+            contents: Contents { model: model.to_string(), t_max, seed, points: Vec::new() },
+        }
+    }
+
+    /// Loads the checkpoint at `path` or starts an empty one if the file does not exist yet.
+    pub fn open<P: AsRef<Path>>(path: P, model: &str, t_max: f64, seed: u64) -> Result<Self, CheckpointError> {
+        let path = path.as_ref().to_path_buf();
+        if !path.exists() {
+            let mut checkpoint = Checkpoint::in_memory(model, t_max, seed);
+            checkpoint.path = Some(path);
+            return Ok(checkpoint);
+        }
+        let contents: Contents<T> = serde_json::from_str(&fs::read_to_string(&path)?)?;
+        if contents.model != model {
+            return Err(CheckpointError::Mismatch(format!("model {} instead of {}", contents.model, model)));
+        }
+        if contents.t_max != t_max {
+            return Err(CheckpointError::Mismatch(format!("t_max {} instead of {}", contents.t_max, t_max)));
+        }
+        //replica i gets the same seed on a restart only if the master seed is the same
+        if contents.seed != seed {
+            return Err(CheckpointError::Mismatch(format!("seed {} instead of {}", contents.seed, seed)));
+        }
+        Ok(Checkpoint { path: Some(path), contents })
+    }
+
+    /// Ranges of the replicas `0..simulations` of the grid point which are not done yet.
+    pub fn missing(&self, point: &[f64], simulations: usize) -> Vec<Range<usize>> {
+        let done = self.contents.points.iter()
+            .find(|(p, _)| p.as_slice() == point)
+            .map_or(&[][..], |(_, p)| p.done.as_slice());
+        let mut missing = Vec::new();
+        let mut next = 0;
+        for &(start, end) in done {
+            if start > next {
+                missing.push(next..start.min(simulations));
+            }
+            next = next.max(end);
+            if next >= simulations {
+                break;
+            }
+        }
+        if next < simulations {
+            missing.push(next..simulations);
+        }
+        missing.retain(|range| !range.is_empty());
+        missing
+    }
+
+    /// Merges the finished batch of the `replicas` into the grid point and saves the checkpoint.
+    pub fn add(&mut self, point: &[f64], replicas: Range<usize>, tally: T) -> Result<(), CheckpointError> {
+        match self.contents.points.iter_mut().find(|(p, _)| p.as_slice() == point) {
+            Some((_, existing)) => {
+                existing.tally.merge(tally);
+                existing.done.push((replicas.start, replicas.end));
+                existing.done.sort_unstable();
+                //adjacent ranges are joined so the file stays small
+                let mut joined: Vec<(usize, usize)> = Vec::with_capacity(existing.done.len());
+                for &(start, end) in &existing.done {
+                    match joined.last_mut() {
+                        Some(last) if last.1 >= start => last.1 = last.1.max(end),
+                        _ => joined.push((start, end)),
+                    }
+                }
+                existing.done = joined;
+            }
+            None => self.contents.points.push((point.to_vec(), Point { done: vec![(replicas.start, replicas.end)], tally })),
+        }
+        self.save()
+    }
+
+    /// Takes the tally of the grid point out of the checkpoint, the file is left untouched.
+    pub fn remove(&mut self, point: &[f64]) -> Option<T> {
+        let index = self.contents.points.iter().position(|(p, _)| p.as_slice() == point)?;
+        Some(self.contents.points.swap_remove(index).1.tally)
+    }
+
+    //written to a temporary file first so an interrupted save keeps the previous checkpoint
+    fn save(&self) -> Result<(), CheckpointError> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        let temporary = path.with_extension("tmp");
+        fs::write(&temporary, serde_json::to_string(&self.contents)?)?;
+        fs::rename(&temporary, path)?;
+        Ok(())
+    }
+}
+
+/// Seed of the sweep saved at `path`, a restarted sweep without a seed of its own continues with it.
+pub fn saved_seed<P: AsRef<Path>>(path: P) -> Result<Option<u64>, CheckpointError> {
+    #[derive(Deserialize)]
+    struct Seed {
+        seed: u64,
+    }
+    if !path.as_ref().exists() {
+        return Ok(None);
+    }
+    let contents: Seed = serde_json::from_str(&fs::read_to_string(path)?)?;
+    Ok(Some(contents.seed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct Count(usize);
+
+    impl Replicas for Count {
+        fn merge(&mut self, other: Self) {
+            self.0 += other.0;
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let path = std::env::temp_dir().join(format!("checkpoint_{}.json", std::process::id()));
+        let _ = fs::remove_file(&path);
+        assert_eq!(saved_seed(&path).unwrap(), None);
+
+        let mut checkpoint = Checkpoint::<Count>::open(&path, "sim1d", 10.0, 7).unwrap();
+        assert_eq!(checkpoint.missing(&[1.5], 300), vec![0..300]);
+        //the second batch finished before the first one
+        checkpoint.add(&[1.5], 100..200, Count(100)).unwrap();
+        checkpoint.add(&[1.6], 0..40, Count(40)).unwrap();
+        checkpoint.add(&[1.5], 250..300, Count(50)).unwrap();
+
+        let mut resumed = Checkpoint::<Count>::open(&path, "sim1d", 10.0, 7).unwrap();
+        assert_eq!(saved_seed(&path).unwrap(), Some(7));
+        assert_eq!(resumed.missing(&[1.5], 300), vec![0..100, 200..250]);
+        assert_eq!(resumed.missing(&[1.5], 220), vec![0..100, 200..220]);
+        assert_eq!(resumed.missing(&[1.5], 400), vec![0..100, 200..250, 300..400]);
+        assert_eq!(resumed.missing(&[1.6], 40), vec![]);
+        resumed.add(&[1.5], 0..100, Count(100)).unwrap();
+        resumed.add(&[1.5], 200..250, Count(50)).unwrap();
+        assert_eq!(resumed.missing(&[1.5], 300), vec![]);
+        assert_eq!(resumed.contents.points[0].1.done, vec![(0, 300)]);
+        assert_eq!(resumed.remove(&[1.5]).map(|c| c.0), Some(300));
+        assert_eq!(resumed.remove(&[1.6]).map(|c| c.0), Some(40));
+        assert!(resumed.remove(&[1.6]).is_none());
+
+        assert!(matches!(Checkpoint::<Count>::open(&path, "sim2d", 10.0, 7), Err(CheckpointError::Mismatch(_))));
+        assert!(matches!(Checkpoint::<Count>::open(&path, "sim1d", 20.0, 7), Err(CheckpointError::Mismatch(_))));
+        assert!(matches!(Checkpoint::<Count>::open(&path, "sim1d", 10.0, 8), Err(CheckpointError::Mismatch(_))));
+        fs::remove_file(&path).unwrap();
+    }
+}
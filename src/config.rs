@@ -0,0 +1,323 @@
+use std::fmt::Display;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use serde::Deserialize;
+
+use crate::analysis::shape::Region;
+use crate::render::frames::Viewport;
+use crate::theory::approximation::{Approximation, Topology};
+
+/// A batch of experiments executed one after the other, read from a TOML or JSON file.
+/// Every experiment names its sweep in the `sweep` field, the other fields are the arguments
+/// of that sweep. Ranges are `[lower, upper]` and use the step size of the sweep.
+/// The initial condition is the one of the sweep, a single infected node for the growth sweeps
+/// and a fully infected lattice for `fss` and `master`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    /// Threads running the replicas, defaults to the available parallelism.
+    #[serde(default = "default_workers")]
+    pub workers: usize,
+    #[serde(rename = "experiment")]
+    pub experiments: Vec<Experiment>,
+}
+
+/// Output paths get the format of their extension, see `output::Format`, and a manifest next to them.
+/// `-` writes CSV to stdout, `.sqlite` and `.db` paths are stores, see `store::Store`.
+/// The `seed` of the seeded sweeps is drawn at random if it is missing, or taken from the
+/// `checkpoint` of a resumed sweep. Their optional `replicas` output gets a record per run,
+/// see `replica::Replica`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "sweep", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Experiment {
+    Simulation {
+        model: String,
+        simulations: i32,
+        t_max: f64,
+        lambda: (f64, f64),
+        checkpoint: Option<String>,
+        replicas: Option<String>,
+        seed: Option<u64>,
+        output: String,
+        extinction_output: Option<String>,
+    },
+    SimulationAlpha {
+        model: String,
+        simulations: i32,
+        t_max: f64,
+        lambda: (f64, f64),
+        alpha: (f64, f64),
+        checkpoint: Option<String>,
+        replicas: Option<String>,
+        seed: Option<u64>,
+        output: String,
+        extinction_output: Option<String>,
+    },
+    Adaptive {
+        model: String,
+        batch_size: i32,
+        max_simulations: i32,
+        target_width: f64,
+        t_max: f64,
+        lambda: (f64, f64),
+        replicas: Option<String>,
+        seed: Option<u64>,
+        output: String,
+    },
+    Fss {
+        simulations: i32,
+        t_relax: f64,
+        t_max: f64,
+        sizes: Vec<usize>,
+        dimensions: usize,
+        alpha: f64,
+        lambda: (f64, f64),
+        seed: Option<u64>,
+        output: String,
+        fit_output: Option<String>,
+    },
+    /// `alpha` is needed by the models that take one.
+    Spreading {
+        model: String,
+        alpha: Option<f64>,
+        simulations: i32,
+        t_max: f64,
+        lambda: f64,
+        seed: Option<u64>,
+        output: String,
+    },
+    Theory {
+        approximation: Approximation,
+        topology: Topology,
+        lambda: (f64, f64),
+        #[serde(default = "default_alpha_range")]
+        alpha: (f64, f64),
+        output: String,
+    },
+    Master {
+        l: usize,
+        dimensions: usize,
+        alpha: f64,
+        t_max: f64,
+        lambda: (f64, f64),
+        output: String,
+    },
+    /// `alpha` is needed by the models that take one.
+    Front {
+        model: String,
+        alpha: Option<f64>,
+        simulations: i32,
+        t_max: f64,
+        dt: f64,
+        lambda: (f64, f64),
+        seed: Option<u64>,
+        output: String,
+    },
+    Shape {
+        simulations: i32,
+        t_max: f64,
+        lambda: f64,
+        alpha: (f64, f64),
+        n_angles: usize,
+        region: Region,
+        seed: Option<u64>,
+        output: String,
+    },
+    /// Space-time diagram of a single run of a 1D model, sampled every `dt`. `.svg` outputs are
+    /// vector graphics, other outputs PNG images with `scale` pixels per site and sample.
+    Spacetime {
+        model: String,
+        alpha: Option<f64>,
+        lambda: f64,
+        t_max: f64,
+        dt: f64,
+        seed: Option<u64>,
+        #[serde(default = "default_scale")]
+        scale: usize,
+        output: String,
+    },
+    /// A single run until `t_max` whose full state is saved to `snapshot`, see `Snapshot`.
+    /// `alpha` is needed by the models that take one.
+    Run {
+        model: String,
+        alpha: Option<f64>,
+        lambda: f64,
+        t_max: f64,
+        seed: Option<u64>,
+        snapshot: String,
+    },
+    /// Continues the run saved in `resume` until `t_max` with the model, parameters and random
+    /// number generator of the snapshot, and saves it again to `snapshot` if it is given.
+    Resume {
+        resume: String,
+        t_max: f64,
+        snapshot: Option<String>,
+    },
+    /// Frames of a single sim2d run every `dt` showing the infected, healed and never infected sites.
+    /// A `.gif` output is an animation, other outputs are numbered PNG or PPM frames whose longer
+    /// side has `size` pixels. The viewport is `"auto"` or e.g. `{ fixed = { x = [-50, 50], y = [-20, 20] } }`.
+    Snapshots {
+        lambda: f64,
+        alpha: f64,
+        t_max: f64,
+        dt: f64,
+        seed: Option<u64>,
+        #[serde(default)]
+        viewport: Viewport,
+        #[serde(default = "default_size")]
+        size: usize,
+        output: String,
+    },
+}
+
+fn default_workers() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+fn default_scale() -> usize {
+    1
+}
+
+fn default_size() -> usize {
+    400
+}
+
+//alpha is ignored for Topology::OneD
+fn default_alpha_range() -> (f64, f64) {
+    (1.0, 1.0)
+}
+
+#[derive(Debug)]
+pub enum ConfigError {
+    Io(io::Error),
+    Toml(toml::de::Error),
+    Json(serde_json::Error),
+    /// The file extension is neither `toml` nor `json`.
+    UnknownFormat(String),
+    NoWorkers,
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(error) => write!(f, "config, {}", error),
+            ConfigError::Toml(error) => write!(f, "invalid config, {}", error),
+            ConfigError::Json(error) => write!(f, "invalid config, {}", error),
+            ConfigError::UnknownFormat(path) => write!(f, "config {} is neither .toml nor .json", path),
+            ConfigError::NoWorkers => write!(f, "config needs at least one worker"),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl From<io::Error> for ConfigError {
+    fn from(error: io::Error) -> Self {
+        ConfigError::Io(error)
+    }
+}
+
+impl Config {
+    /// The format is taken from the file extension.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("toml") => Config::from_toml(&text),
+            Some("json") => Config::from_json(&text),
+            _ => Err(ConfigError::UnknownFormat(path.display().to_string())),
+        }
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, ConfigError> {
+        toml::from_str::<Config>(text).map_err(ConfigError::Toml)?.checked()
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, ConfigError> {
+        serde_json::from_str::<Config>(text).map_err(ConfigError::Json)?.checked()
+    }
+
+    //the parameters of the experiments are checked by the sweeps
+    fn checked(self) -> Result<Self, ConfigError> {
+        if self.workers == 0 {
+            return Err(ConfigError::NoWorkers);
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = Config::from_toml(r#"
+            workers = 4
+
+            [[experiment]]
+            sweep = "simulation"
+            model = "sim1d_rb"
+            simulations = 1000
+            t_max = 1000.0
+            lambda = [1.86, 2.0]
+            seed = 7
+            output = "results_rb.csv"
+
+            [[experiment]]
+            sweep = "shape"
+            simulations = 50
+            t_max = 100.0
+            lambda = 2.5
+            alpha = [0.1, 0.5]
+            n_angles = 16
+            region = "ever_infected"
+            output = "shape.csv"
+        "#).unwrap();
+        assert_eq!(config.workers, 4);
+        assert_eq!(config.experiments.len(), 2);
+        match &config.experiments[0] {
+            Experiment::Simulation { model, lambda, checkpoint, seed, .. } => {
+                assert_eq!(model, "sim1d_rb");
+                assert_eq!(*lambda, (1.86, 2.0));
+                assert!(checkpoint.is_none());
+                assert_eq!(*seed, Some(7));
+            }
+            other => panic!("parsed as {:?}", other),
+        }
+        assert!(matches!(config.experiments[1], Experiment::Shape { region: Region::EverInfected, .. }));
+
+        let config = Config::from_toml(r#"
+            [[experiment]]
+            sweep = "snapshots"
+            lambda = 2.0
+            alpha = 0.8
+            t_max = 50.0
+            dt = 1.0
+            viewport = { fixed = { x = [-50, 50], y = [-20, 20] } }
+            output = "alpha08.gif"
+        "#).unwrap();
+        assert!(matches!(config.experiments[0],
+            Experiment::Snapshots { viewport: Viewport::Fixed { x: (-50, 50), y: (-20, 20) }, size: 400, .. }));
+
+        let config = Config::from_toml(r#"
+            [[experiment]]
+            sweep = "resume"
+            resume = "run.msgpack"
+            t_max = 200.0
+        "#).unwrap();
+        assert!(matches!(&config.experiments[0], Experiment::Resume { resume, snapshot: None, .. } if resume == "run.msgpack"));
+
+        let config = Config::from_json(r#"{"experiment": [{"sweep": "theory", "approximation": "pair",
+            "topology": "one_d", "lambda": [1.0, 2.0], "output": "theory.csv"}]}"#).unwrap();
+        assert!(config.workers > 0);
+        assert!(matches!(config.experiments[0], Experiment::Theory { alpha: (1.0, 1.0), .. }));
+
+        //misspelled fields are errors instead of silently using defaults
+        assert!(Config::from_json(r#"{"experiment": [{"sweep": "theory", "approximation": "pair",
+            "topology": "one_d", "lambda": [1.0, 2.0], "output": "theory.csv", "apha": [0.1, 0.2]}]}"#).is_err());
+        assert!(matches!(Config::from_json(r#"{"workers": 0, "experiment": []}"#), Err(ConfigError::NoWorkers)));
+    }
+}
@@ -1,17 +1,56 @@
+use std::cell::Cell;
+use std::error::Error;
 use std::fmt::Display;
 use crate::simulation::sim::*;
 use threadpool::ThreadPool;
-use std::sync::mpsc;
+use std::sync::mpsc::{self, Sender};
 use rand::Rng;
 
 mod simulation;
+mod analysis;
+mod theory;
+mod sweep;
+mod checkpoint;
+mod manifest;
+mod config;
+mod output;
+mod store;
+mod replica;
+mod render;
 
 use simulation::*;
+use analysis::front::estimate_velocity;
+use analysis::shape::{estimate_shape, Region};
+use analysis::outbreak::OutbreakAverage;
+use analysis::extinction::{ExtinctionTimes, HISTOGRAM_BINS};
+use analysis::stats::Uncertainty;
+use analysis::sums::RunSums;
+use analysis::fss::{measure, FssFit, FssPoint, Moments};
+use analysis::spreading::{batch_sizes, log_times, SpreadingFit, SpreadingPoint, SpreadingSums};
+use theory::approximation::{stationary_density, threshold, Approximation, Topology};
+use theory::master::{MasterEquation, MAX_SITES};
+use sweep::{execute, replica_seed, FailureReason, Sweep, SweepError};
+use checkpoint::{Checkpoint, CheckpointError, Replicas};
+use manifest::Manifest;
+use config::{Config, Experiment};
+use output::RecordWriter;
+use store::Store;
+use replica::{Replica, ReplicaStream};
+use render::spacetime::SpaceTime;
+use render::frames::{self, Frame};
+use simulation::registry::Model;
+use simulation::snapshot::Snapshot;
+use serde::{Deserialize, Serialize};
+use simulation::params::{ParamError, Parameter, ALPHA, BATCH_SIZE, DIMENSIONS, DT, L, LAMBDA, N_ANGLES, SIMULATIONS, TARGET_WIDTH, T_MAX};
 
 struct SimulationResult {
     lambda: f64,
     success_average: f64,
     end_nodes_average: f64,
+    uncertainty: Uncertainty,
+    outbreak: OutbreakAverage,
+    sums: RunSums,
+    extinction: ExtinctionTimes,
 }
 
 struct SimulationResultAlpha {
@@ -19,186 +58,1444 @@ struct SimulationResultAlpha {
     alpha: f64,
     success_average: f64,
     end_nodes_average: f64,
+    uncertainty: Uncertainty,
+    outbreak: OutbreakAverage,
+    sums: RunSums,
+    extinction: ExtinctionTimes,
 }
 
-trait WriteToCsv {
-    fn write_to_csv(&self, wtr: &mut csv::Writer<std::fs::File>);
-    fn write_header_to_csv(wtr: &mut csv::Writer<std::fs::File>);
+/// Accumulates the runs of one grid point, tallies of separate batches can be merged.
+#[derive(Serialize, Deserialize)]
+struct Tally {
+    sums: RunSums,
+    extinction: ExtinctionTimes,
+}
+
+impl Tally {
+    fn new(t_max: f64) -> Self {
+        Tally {
+            sums: RunSums::default(),
+            extinction: ExtinctionTimes::new(t_max),
+        }
+    }
+
+    //died is the result of Sim::run
+    fn add(&mut self, sim: &dyn Sim, died: bool) {
+        self.sums.add(died, sim.get_number_of_infected_nodes(), sim.get_outbreak_stats());
+        self.extinction.add(died, sim.get_t());
+    }
+
+    fn into_result(self, lambda: f64) -> SimulationResult {
+        SimulationResult::new(lambda, self.sums, self.extinction)
+    }
+
+    fn into_result_alpha(self, lambda: f64, alpha: f64) -> SimulationResultAlpha {
+        SimulationResultAlpha::new(lambda, alpha, self.sums, self.extinction)
+    }
+}
+
+impl SimulationResult {
+    //every average is derived from the sums, which are written along with them
+    fn new(lambda: f64, sums: RunSums, extinction: ExtinctionTimes) -> Self {
+        SimulationResult {
+            lambda,
+            success_average: sums.success_average(),
+            end_nodes_average: sums.end_nodes_average(),
+            uncertainty: sums.uncertainty(),
+            outbreak: sums.outbreak(),
+            sums,
+            extinction,
+        }
+    }
+}
+
+impl SimulationResultAlpha {
+    fn new(lambda: f64, alpha: f64, sums: RunSums, extinction: ExtinctionTimes) -> Self {
+        SimulationResultAlpha {
+            lambda,
+            alpha,
+            success_average: sums.success_average(),
+            end_nodes_average: sums.end_nodes_average(),
+            uncertainty: sums.uncertainty(),
+            outbreak: sums.outbreak(),
+            sums,
+            extinction,
+        }
+    }
+}
+
+impl Replicas for Tally {
+    fn merge(&mut self, other: Tally) {
+        self.sums.merge(&other.sums);
+        self.extinction.merge(other.extinction);
+    }
+}
+
+struct ExtinctionResult<'a> {
+    lambda: f64,
+    alpha: Option<f64>,
+    times: &'a ExtinctionTimes,
+}
+
+impl SimulationResult {
+    fn extinction_result(&self) -> ExtinctionResult<'_> {
+        ExtinctionResult { lambda: self.lambda, alpha: None, times: &self.extinction }
+    }
+}
+
+impl SimulationResultAlpha {
+    fn extinction_result(&self) -> ExtinctionResult<'_> {
+        ExtinctionResult { lambda: self.lambda, alpha: Some(self.alpha), times: &self.extinction }
+    }
+}
+
+//the survival probability of a single seed equals the stationary density (self duality)
+struct TheoryResult {
+    lambda: f64,
+    alpha: Option<f64>,
+    approximation: Approximation,
+    density: f64,
+    threshold: Option<f64>,
+}
+
+//exact results of the finite system starting fully infected, like run_fss_simulation
+struct MasterResult {
+    l: usize,
+    lambda: f64,
+    mean_absorption_time: Option<f64>,
+    decay_rate: Option<f64>,
+    density: Option<f64>,
+    survival: Vec<(f64, f64)>,
+}
+
+struct FrontResult {
+    lambda: f64,
+    surviving: usize,
+    velocity: f64,
+    velocity_std_err: f64,
+}
+
+struct ShapeResult {
+    lambda: f64,
+    alpha: f64,
+    surviving: usize,
+    angle: f64,
+    radius: f64,
+    radius_std_err: f64,
+}
+
+trait WriteRecords {
+    /// Tells the result types apart in a store, see `store::Store::add_run`.
+    const KIND: &'static str;
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()>;
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()>;
+    /// Only result types whose columns depend on the results need their own.
+    fn write_header_for(_results: &[Self], wtr: &mut dyn RecordWriter) -> std::io::Result<()> where Self: Sized {
+        Self::write_header(wtr)
+    }
 }
 impl Display for SimulationResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f,
-               "lambda {}, disease died percentage {}, average of nodes at end of simulation {}",
+               "lambda {}, disease died percentage {} {:?}, average of nodes at end of simulation {} +- {}, \
+               average of ever infected nodes {}, average peak {} at t {}",
                self.lambda,
                self.success_average,
-               self.end_nodes_average)
+               self.uncertainty.wilson(),
+               self.end_nodes_average,
+               self.uncertainty.end_nodes_std_err(),
+               self.outbreak.ever_infected,
+               self.outbreak.peak,
+               self.outbreak.peak_t)
     }
 }
 
 impl Display for SimulationResultAlpha {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f,
-               "lambda {}, alpha {}, disease died percentage {}, average of nodes at end of simulation {}",
+               "lambda {}, alpha {}, disease died percentage {} {:?}, average of nodes at end of simulation {} +- {}, \
+               average of ever infected nodes {}, average peak {} at t {}",
                self.lambda,
                self.alpha,
                self.success_average,
-               self.end_nodes_average)
+               self.uncertainty.wilson(),
+               self.end_nodes_average,
+               self.uncertainty.end_nodes_std_err(),
+               self.outbreak.ever_infected,
+               self.outbreak.peak,
+               self.outbreak.peak_t)
     }
 }
 
-impl WriteToCsv for SimulationResultAlpha {
-    fn write_to_csv(&self, wtr: &mut csv::Writer<std::fs::File>) {
-        wtr.write_record(&[
+impl Display for FrontResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "lambda {}, surviving runs {}, front velocity {} +- {}",
+               self.lambda,
+               self.surviving,
+               self.velocity,
+               self.velocity_std_err)
+    }
+}
+
+impl Display for ShapeResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "lambda {}, alpha {}, surviving runs {}, angle {}, radius {} +- {}",
+               self.lambda,
+               self.alpha,
+               self.surviving,
+               self.angle,
+               self.radius,
+               self.radius_std_err)
+    }
+}
+
+impl WriteRecords for SimulationResultAlpha {
+    const KIND: &'static str = "simulation_alpha";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut record = vec![
             self.lambda.to_string(),
             self.alpha.to_string(),
             self.success_average.to_string(),
-            self.end_nodes_average.to_string()]).unwrap();
+            self.end_nodes_average.to_string()];
+        record.extend(self.uncertainty.csv_record());
+        record.extend(self.outbreak.csv_record());
+        record.extend(self.sums.csv_record());
+        wtr.write_record(&record)
     }
-    fn write_header_to_csv(wtr: &mut csv::Writer<std::fs::File>) {
-        wtr.write_record(&["Lambda", "Alpha", "Disease died average", "End Nodes Average"]).unwrap();
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut header = vec!["Lambda", "Alpha", "Disease died average", "End Nodes Average"];
+        header.extend(Uncertainty::csv_header());
+        header.extend(OutbreakAverage::csv_header());
+        header.extend(RunSums::csv_header());
+        wtr.write_header(&header)
     }
 }
 
-impl WriteToCsv for SimulationResult {
-    fn write_to_csv(&self, wtr: &mut csv::Writer<std::fs::File>) {
-        wtr.write_record(&[
+impl WriteRecords for SimulationResult {
+    const KIND: &'static str = "simulation";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut record = vec![
             self.lambda.to_string(),
             self.success_average.to_string(),
-            self.end_nodes_average.to_string()]).unwrap();
+            self.end_nodes_average.to_string()];
+        record.extend(self.uncertainty.csv_record());
+        record.extend(self.outbreak.csv_record());
+        record.extend(self.sums.csv_record());
+        wtr.write_record(&record)
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut header = vec!["Lambda", "Disease died average", "End Nodes Average"];
+        header.extend(Uncertainty::csv_header());
+        header.extend(OutbreakAverage::csv_header());
+        header.extend(RunSums::csv_header());
+        wtr.write_header(&header)
+    }
+}
+
+
+
+//long format, one row per statistic, histogram bin or point of the survival curve
+impl WriteRecords for ExtinctionResult<'_> {
+    const KIND: &'static str = "extinction";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let alpha = self.alpha.map(|a| a.to_string()).unwrap_or_default();
+        let mut write_row = |kind: &str, t: String, value: String| {
+            wtr.write_record(&[self.lambda.to_string(), alpha.clone(), kind.to_string(), t, value])
+        };
+        let times = self.times;
+        write_row("runs", String::new(), times.runs().to_string())?;
+        write_row("censored", times.t_max.to_string(), times.censored.to_string())?;
+        if let Some(mean) = times.mean() {
+            write_row("mean", String::new(), mean.to_string())?;
+        }
+        if let Some(median) = times.median() {
+            write_row("median", String::new(), median.to_string())?;
+        }
+        let width = times.t_max / HISTOGRAM_BINS as f64;
+        for (i, count) in times.histogram(HISTOGRAM_BINS).iter().enumerate() {
+            write_row("histogram", (i as f64 * width).to_string(), count.to_string())?;
+        }
+        for (t, survival) in times.kaplan_meier() {
+            write_row("survival", t.to_string(), survival.to_string())?;
+        }
+        Ok(())
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["Lambda", "Alpha", "Kind", "T", "Value"])
+    }
+}
+
+
+
+impl WriteRecords for FssPoint {
+    const KIND: &'static str = "fss";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_record(&[
+            self.l.to_string(),
+            self.lambda.to_string(),
+            self.order_parameter.to_string(),
+            self.susceptibility.to_string(),
+            self.moment_ratio.to_string(),
+            self.binder_cumulant.to_string(),
+            self.collapse_residual.to_string()])
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["L", "Lambda", "Order Parameter", "Susceptibility", "Moment Ratio",
+            "Binder Cumulant", "Collapse Residual"])
+    }
+}
+
+impl WriteRecords for FssFit {
+    const KIND: &'static str = "fss_fit";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_record(&[
+            self.lambda_c_crossing.to_string(),
+            self.lambda_c.to_string(),
+            self.beta_over_nu.to_string(),
+            self.nu_perp.to_string(),
+            self.residual.to_string()])
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["Lambda C Crossing", "Lambda C", "Beta Over Nu", "Nu Perp", "Residual"])
+    }
+}
+
+impl Display for FssFit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "lambda_c from crossings {}, lambda_c {}, beta/nu {}, nu_perp {}, residual {}",
+               self.lambda_c_crossing,
+               self.lambda_c,
+               self.beta_over_nu,
+               self.nu_perp,
+               self.residual)
+    }
+}
+
+impl WriteRecords for SpreadingPoint {
+    const KIND: &'static str = "spreading";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut record = vec![
+            self.t.to_string(),
+            self.survival.to_string(),
+            self.infected.to_string(),
+            self.squared_radius.to_string()];
+        for (exponent, error) in self.exponents {
+            record.push(exponent.to_string());
+            record.push(error.to_string());
+        }
+        wtr.write_record(&record)
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["T", "Survival", "Infected", "Squared Radius", "Delta", "Delta Err",
+            "Eta", "Eta Err", "Z", "Z Err"])
+    }
+}
+
+impl Display for SpreadingFit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "delta {} +- {}, eta {} +- {}, z {} +- {}",
+               self.delta.0, self.delta.1,
+               self.eta.0, self.eta.1,
+               self.z.0, self.z.1)
+    }
+}
+
+impl Display for TheoryResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "{} approximation lambda {}, alpha {:?}, stationary density {}, threshold {:?}",
+               self.approximation.name(),
+               self.lambda,
+               self.alpha,
+               self.density,
+               self.threshold)
     }
-    fn write_header_to_csv(wtr: &mut csv::Writer<std::fs::File>) {
-        wtr.write_record(&["Lambda", "Disease died average", "End Nodes Average"]).unwrap();
+}
+
+//leading columns follow the simulation results so the curves can be plotted together,
+//the 1D approximations have no alpha column like the results of run_simulation
+impl WriteRecords for TheoryResult {
+    const KIND: &'static str = "theory";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let mut record = vec![self.lambda.to_string()];
+        record.extend(self.alpha.map(|a| a.to_string()));
+        record.extend([
+            (1.0 - self.density).to_string(),
+            self.density.to_string(),
+            self.threshold.map(|t| t.to_string()).unwrap_or_default(),
+            self.approximation.name().to_string()]);
+        wtr.write_record(&record)
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["Lambda", "Alpha", "Disease died average", "Density", "Threshold", "Approximation"])
+    }
+    fn write_header_for(results: &[Self], wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        if results.iter().all(|r| r.alpha.is_none()) && !results.is_empty() {
+            wtr.write_header(&["Lambda", "Disease died average", "Density", "Threshold", "Approximation"])
+        } else {
+            Self::write_header(wtr)
+        }
+    }
+}
+
+impl Display for MasterResult {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f,
+               "L {}, lambda {}, mean absorption time {:?}, decay rate {:?}, quasi stationary density {:?}",
+               self.l,
+               self.lambda,
+               self.mean_absorption_time,
+               self.decay_rate,
+               self.density)
     }
 }
 
+//one row per sample time of the survival probability
+impl WriteRecords for MasterResult {
+    const KIND: &'static str = "master";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        let optional = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        for &(t, survival) in &self.survival {
+            wtr.write_record(&[
+                self.l.to_string(),
+                self.lambda.to_string(),
+                t.to_string(),
+                survival.to_string(),
+                optional(self.mean_absorption_time),
+                optional(self.decay_rate),
+                optional(self.density)])?;
+        }
+        Ok(())
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["L", "Lambda", "T", "Survival", "Mean Absorption Time", "Decay Rate", "Quasi Stationary Density"])
+    }
+}
 
+impl WriteRecords for FrontResult {
+    const KIND: &'static str = "front";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_record(&[
+            self.lambda.to_string(),
+            self.surviving.to_string(),
+            self.velocity.to_string(),
+            self.velocity_std_err.to_string()])
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["Lambda", "Surviving", "Velocity", "Velocity Std Err"])
+    }
+}
 
-//step size is 0.01
-fn run_simulation(simulations: i32, t_max: f64, lambda_range: (f64, f64)) -> Vec<SimulationResult>{
-    let mut simulation_results = Vec::new();
-    let n_workers = 12;
+
+
+impl WriteRecords for ShapeResult {
+    const KIND: &'static str = "shape";
+    fn write_records(&self, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_record(&[
+            self.lambda.to_string(),
+            self.alpha.to_string(),
+            self.surviving.to_string(),
+            self.angle.to_string(),
+            self.radius.to_string(),
+            self.radius_std_err.to_string()])
+    }
+    fn write_header(wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+        wtr.write_header(&["Lambda", "Alpha", "Surviving", "Angle", "Radius", "Radius Std Err"])
+    }
+}
+
+
+
+//replicas are run in batches of this size, a resumed sweep loses at most one batch per worker
+const CHECKPOINT_BATCH: usize = 100;
+
+//"Lambda: 1.5, Alpha: 0.3"
+fn describe(model: &Model, point: &[f64]) -> String {
+    let values: Vec<String> = model.parameter_names().iter().zip(point)
+        .map(|(name, value)| format!("{}{}: {}", name[..1].to_uppercase(), &name[1..], value))
+        .collect();
+    values.join(", ")
+}
+
+//one replica of a batch, its record goes to the replica stream if there is one
+fn run_replica(model: &Model,
+               point: &[f64],
+               replica: usize,
+               seed: u64,
+               t_max: f64,
+               tally: &mut Tally,
+               replicas: Option<&Sender<Replica>>) {
+    let seed = replica_seed(seed, point, replica);
+    let mut sim = model.create_with_seed(point, seed).expect("grid point was validated");
+    let (died, events) = sim.run_counting(t_max);
+    tally.add(sim.as_ref(), died);
+    if let Some(replicas) = replicas {
+        //sending only fails if the writer stopped, closing the stream reports why
+        let _ = replicas.send(Replica::new(point, replica, seed, sim.as_ref(), died, events));
+    }
+}
+
+//runs every grid point until it has `simulations` replicas, every finished batch is merged into
+//the checkpoint, so grid points which already have enough replicas are skipped on a restart and
+//a larger `simulations` only runs the missing ones. Invalid or panicked grid points are left out.
+//Every replica run here is written to the replicas output if one is given.
+#[allow(clippy::too_many_arguments)]
+fn run_grid<R>(model: &'static Model,
+               points: Vec<Vec<f64>>,
+               simulations: i32,
+               t_max: f64,
+               checkpoint: Option<&str>,
+               replicas: Option<&str>,
+               seed: u64,
+               n_workers: usize,
+               sweep: &mut Sweep<R>
+               ) -> Result<Vec<(Vec<f64>, Tally)>, SweepError> {
+    let mut checkpoint = match checkpoint {
+        Some(path) => Checkpoint::open(path, model.name, t_max, seed)?,
+        None => Checkpoint::in_memory(model.name, t_max, seed),
+    };
+    let stream = replicas.map(|path| ReplicaStream::spawn(path, sweep.manifest.clone()));
     let (tx, rx) = mpsc::channel();
     let pool = ThreadPool::new(n_workers);
 
+    let mut valid = vec![false; points.len()];
+    for (index, point) in points.iter().enumerate() {
+        if let Err(error) = model.validate(point) {
+            sweep.fail(describe(model, point), FailureReason::InvalidParameters(error));
+            continue;
+        }
+        valid[index] = true;
+        //replica i of a grid point always gets the same seed, a resumed sweep runs the ones it lacks
+        let missing = checkpoint.missing(point, simulations.max(0) as usize);
+        if missing.is_empty() {
+            eprintln!("{}, done", describe(model, point));
+        }
+        for range in missing {
+            for first in range.clone().step_by(CHECKPOINT_BATCH) {
+                let batch = first..(first + CHECKPOINT_BATCH).min(range.end);
+                let point = point.clone();
+                let label = describe(model, &point);
+                let replicas = stream.as_ref().map(ReplicaStream::sender);
+                execute(&pool, &tx, (index, batch.clone()), move || {
+                    let mut tally = Tally::new(t_max);
+                    eprintln!("{}, Runs: {}", label, batch.len());
+                    for replica in batch {
+                        run_replica(model, &point, replica, seed, t_max, &mut tally, replicas.as_ref());
+                    }
+                    tally
+                });
+            }
+        }
+    }
+    drop(tx);
+    let mut failed = vec![false; points.len()];
+    //a checkpoint that can't be written ends the sweep, but only once the workers are done
+    let mut checkpoint_error = None;
+    for ((index, batch), result) in rx {
+        match result {
+            Ok(tally) if checkpoint_error.is_none() => {
+                if let Err(error) = checkpoint.add(&points[index], batch, tally) {
+                    checkpoint_error = Some(error);
+                }
+            }
+            Ok(_) => {}
+            Err(message) => {
+                if !failed[index] {
+                    sweep.fail(describe(model, &points[index]), FailureReason::Panicked(message));
+                }
+                failed[index] = true;
+            }
+        }
+    }
+    pool.join();
+    let closed = stream.map_or(Ok(()), ReplicaStream::close);
+    if let Some(error) = checkpoint_error {
+        return Err(error.into());
+    }
+    closed?;
+
+    let mut tallies = Vec::new();
+    for (index, point) in points.into_iter().enumerate() {
+        if valid[index] && !failed[index] {
+            if let Some(tally) = checkpoint.remove(&point) {
+                tallies.push((point, tally));
+            }
+        }
+    }
+    Ok(tallies)
+}
+
+//step size is 0.01, the model is looked up in the registry and must only take lambda,
+//finished replicas are kept in the checkpoint file if one is given
+#[allow(clippy::too_many_arguments)]
+fn run_simulation(model: &str,
+                  simulations: i32,
+                  t_max: f64,
+                  lambda_range: (f64, f64),
+                  checkpoint: Option<&str>,
+                  replicas: Option<&str>,
+                  seed: u64,
+                  n_workers: usize
+                  ) -> Result<Sweep<SimulationResult>, SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    let mut sweep = Sweep::new(Manifest::new("run_simulation")
+        .model(model.name)
+        .parameter("simulations", simulations)
+        .parameter("t_max", t_max)
+        .parameter("lambda_range", lambda_range)
+        .parameter("checkpoint", checkpoint)
+        .parameter("replicas", replicas)
+        .seed(seed));
+
     let lower_bound = (lambda_range.0 * 100.0) as i32;
     let upper_bound = (lambda_range.1 * 100.0) as i32;
 
-    let simulations = simulations;
+    let points = (lower_bound..=upper_bound).map(|lambda| vec![lambda as f64 / 100.0]).collect();
+    for (point, tally) in run_grid(model, points, simulations, t_max, checkpoint, replicas, seed, n_workers, &mut sweep)? {
+        sweep.results.push(tally.into_result(point[0]));
+    }
+
+    sweep.results.sort_by(|a, b| a.lambda.partial_cmp(&b.lambda).unwrap());
+
+    Ok(sweep)
+}
+
+
+//the model is looked up in the registry and must take lambda and alpha,
+//finished replicas are kept in the checkpoint file if one is given
+#[allow(clippy::too_many_arguments)]
+fn run_simulation_alpha(model: &str,
+                        simulations: i32,
+                        t_max: f64,
+                        lambda_range: (f64, f64),
+                        alpha_range: (f64, f64),
+                        checkpoint: Option<&str>,
+                        replicas: Option<&str>,
+                        seed: u64,
+                        n_workers: usize
+                        ) -> Result<Sweep<SimulationResultAlpha>, SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    let mut sweep = Sweep::new(Manifest::new("run_simulation_alpha")
+        .model(model.name)
+        .parameter("simulations", simulations)
+        .parameter("t_max", t_max)
+        .parameter("lambda_range", lambda_range)
+        .parameter("alpha_range", alpha_range)
+        .parameter("checkpoint", checkpoint)
+        .parameter("replicas", replicas)
+        .seed(seed));
+
+    let lower_bound = (lambda_range.0 * 100.0) as i32;
+    let upper_bound = (lambda_range.1 * 100.0) as i32;
+
+    let lower_bound_alpha = (alpha_range.0 * 10.0) as i32;
+    let upper_bound_alpha = (alpha_range.1 * 10.0) as i32;
+
+    let mut points = Vec::new();
     for lambda in lower_bound..=upper_bound {
-        let tx = tx.clone();
+        for alpha in lower_bound_alpha..=upper_bound_alpha {
+            points.push(vec![lambda as f64 / 100.0, alpha as f64 / 10.0]);
+        }
+    }
+    for (point, tally) in run_grid(model, points, simulations, t_max, checkpoint, replicas, seed, n_workers, &mut sweep)? {
+        sweep.results.push(tally.into_result_alpha(point[0], point[1]));
+    }
 
-        pool.execute(move || {
+    //sort by alpha and lambda
+    sweep.results.sort_by(|a, b| {
+        if a.lambda == b.lambda {
+            a.alpha.partial_cmp(&b.alpha).unwrap()
+        } else {
+            a.lambda.partial_cmp(&b.lambda).unwrap()
+        }
+    });
+    Ok(sweep)
+}
+
+
+
+
+
+
+//step size is 0.01, the model must only take lambda like in run_simulation
+//every lambda gets batches of batch_size runs until the width of the confidence
+//interval of the disease died average is below target_width or max_simulations is reached
+#[allow(clippy::too_many_arguments)]
+fn run_simulation_adaptive(model: &str,
+                           batch_size: i32,
+                           max_simulations: i32,
+                           target_width: f64,
+                           t_max: f64,
+                           lambda_range: (f64, f64),
+                           replicas: Option<&str>,
+                           seed: u64,
+                           n_workers: usize
+                           ) -> Result<Sweep<SimulationResult>, SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    //an empty batch would be rescheduled forever
+    BATCH_SIZE.check(batch_size as f64)?;
+    if max_simulations < batch_size {
+        return Err(ParamError::OutOfRange { name: "max_simulations", value: max_simulations as f64, min: batch_size as f64, max: f64::INFINITY }.into());
+    }
+    TARGET_WIDTH.check(target_width)?;
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPool::new(n_workers);
+
+    let lower_bound = (lambda_range.0 * 100.0) as i32;
+    let upper_bound = (lambda_range.1 * 100.0) as i32;
+    let mut sweep = Sweep::new(Manifest::new("run_simulation_adaptive")
+        .model(model.name)
+        .parameter("batch_size", batch_size)
+        .parameter("max_simulations", max_simulations)
+        .parameter("target_width", target_width)
+        .parameter("t_max", t_max)
+        .parameter("lambda_range", lambda_range)
+        .parameter("replicas", replicas)
+        .seed(seed));
+    let stream = replicas.map(|path| ReplicaStream::spawn(path, sweep.manifest.clone()));
+    let mut lambdas = Vec::new();
+    for lambda in (lower_bound..=upper_bound).map(|l| l as f64 / 100.0) {
+        match model.validate(&[lambda]) {
+            Ok(()) => lambdas.push(lambda),
+            Err(error) => sweep.fail(format!("Lambda: {}", lambda), FailureReason::InvalidParameters(error)),
+        }
+    }
+
+    //first is the index of the first replica in the batch, see sweep::replica_seed
+    let run_batch = |index: usize, lambda: f64, first: usize| {
+        let replicas = stream.as_ref().map(ReplicaStream::sender);
+        execute(&pool, &tx, index, move || {
+            let mut tally = Tally::new(t_max);
+            for replica in first..first + batch_size as usize {
+                run_replica(model, &[lambda], replica, seed, t_max, &mut tally, replicas.as_ref());
+            }
+            tally
+        });
+    };
+
+    let mut tallies: Vec<Tally> = lambdas.iter().map(|_| Tally::new(t_max)).collect();
+    let mut scheduled = vec![batch_size as usize; lambdas.len()];
+    let mut running = 0;
+    for (index, &lambda) in lambdas.iter().enumerate() {
+        run_batch(index, lambda, 0);
+        running += 1;
+    }
+
+    //converged grid points get no new batches, so the free threads pick up the uncertain ones,
+    //a grid point whose batch panicked gets no new batches either
+    let mut failed = vec![false; lambdas.len()];
+    while running > 0 {
+        let (index, result) = rx.recv().unwrap();
+        running -= 1;
+        let batch = match result {
+            Ok(batch) => batch,
+            Err(message) => {
+                sweep.fail(format!("Lambda: {}", lambdas[index]), FailureReason::Panicked(message));
+                failed[index] = true;
+                continue;
+            }
+        };
+        let tally = &mut tallies[index];
+        tally.merge(batch);
+        let (lower, upper) = tally.sums.uncertainty().wilson();
+        eprintln!("Lambda: {}, Runs: {}, Interval width: {}", lambdas[index], tally.sums.runs, upper - lower);
+        if upper - lower > target_width && tally.sums.runs < max_simulations as usize {
+            run_batch(index, lambdas[index], scheduled[index]);
+            scheduled[index] += batch_size as usize;
+            running += 1;
+        }
+    }
+    pool.join();
+    if let Some(stream) = stream {
+        stream.close()?;
+    }
+
+    sweep.results = lambdas.into_iter()
+        .zip(tallies)
+        .zip(failed)
+        .filter(|(_, failed)| !failed)
+        .map(|((lambda, tally), _)| tally.into_result(lambda))
+        .collect();
+    Ok(sweep)
+}
+
+
+
+//step size is 0.01, every system starts fully infected and is measured between t_relax and t_max
+#[allow(clippy::too_many_arguments)]
+fn run_fss_simulation(simulations: i32,
+                      t_relax: f64,
+                      t_max: f64,
+                      sizes: &[usize],
+                      dimensions: usize,
+                      alpha: f64,
+                      lambda_range: (f64, f64),
+                      seed: u64,
+                      n_workers: usize
+                      ) -> Result<Sweep<FssPoint>, SweepError> {
+    SIMULATIONS.check(simulations as f64)?;
+    T_MAX.check(t_max)?;
+    T_MAX.check(t_relax)?;
+    DIMENSIONS.check(dimensions as f64)?;
+    ALPHA.check(alpha)?;
+    for &l in sizes {
+        L.check(l as f64)?;
+    }
+    let mut sweep = Sweep::new(Manifest::new("run_fss_simulation")
+        .parameter("simulations", simulations)
+        .parameter("t_relax", t_relax)
+        .parameter("t_max", t_max)
+        .parameter("sizes", sizes)
+        .parameter("dimensions", dimensions)
+        .parameter("alpha", alpha)
+        .parameter("lambda_range", lambda_range)
+        .seed(seed));
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPool::new(n_workers);
+
+    let lower_bound = (lambda_range.0 * 100.0) as i32;
+    let upper_bound = (lambda_range.1 * 100.0) as i32;
+
+    for &l in sizes {
+        for lambda in lower_bound..=upper_bound {
             let lambda = lambda as f64 / 100.0;
-            let mut success = 0;
-            let mut end_nodes_sum = 0;  
-            println!("Lambda: {}", lambda);
-            for _i in 0..simulations {
-                let mut sim = sim1d_rb::Simulation::new(lambda);
-                let result = sim.run(t_max);
-                if result {
-                    success += 1;
+            if let Err(error) = LAMBDA.check(lambda) {
+                sweep.fail(format!("L: {}, Lambda: {}", l, lambda), FailureReason::InvalidParameters(error));
+                continue;
+            }
+            execute(&pool, &tx, (l, lambda), move || {
+                eprintln!("L: {}, Lambda: {}", l, lambda);
+                let mut moments = Moments::default();
+                for replica in 0..simulations as usize {
+                    let seed = replica_seed(seed, &[l as f64, lambda], replica);
+                    let mut sim = finite::Simulation::with_seed(l, dimensions, lambda, alpha, seed);
+                    moments.merge(measure(&mut sim, t_relax, t_max));
                 }
-                end_nodes_sum += sim.get_number_of_infected_nodes();
+                FssPoint::new(l, dimensions, lambda, &moments)
+            });
+        }
+    }
+    pool.join();
+    drop(tx);
+    for ((l, lambda), result) in rx {
+        match result {
+            Ok(point) => sweep.results.push(point),
+            Err(message) => sweep.fail(format!("L: {}, Lambda: {}", l, lambda), FailureReason::Panicked(message)),
+        }
+    }
+
+    //sort by size and lambda
+    sweep.results.sort_by(|a, b| {
+        if a.l == b.l {
+            a.lambda.partial_cmp(&b.lambda).unwrap()
+        } else {
+            a.l.cmp(&b.l)
+        }
+    });
+    Ok(sweep)
+}
+
+
+
+//single seed runs at a fixed lambda, sampled at logarithmically spaced times,
+//every worker runs one batch and the batches give the jackknife errors.
+//The model is looked up in the registry, alpha is needed by the models that take one
+fn run_spreading_simulation(model: &str,
+                            alpha: Option<f64>,
+                            simulations: i32,
+                            t_max: f64,
+                            lambda: f64,
+                            seed: u64,
+                            n_workers: usize
+                            ) -> Result<(Sweep<SpreadingPoint>, SpreadingFit), SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    let point: Vec<f64> = std::iter::once(lambda).chain(alpha).collect();
+    model.validate(&point)?;
+    let n_batches = 20;
+    //the jackknife needs every batch, the remainder is spread over the first ones
+    if simulations < n_batches as i32 {
+        return Err(ParamError::OutOfRange { name: "simulations", value: simulations as f64, min: n_batches as f64, max: f64::INFINITY }.into());
+    }
+    let mut sweep = Sweep::new(Manifest::new("run_spreading_simulation")
+        .model(model.name)
+        .parameter("alpha", alpha)
+        .parameter("simulations", simulations)
+        .parameter("t_max", t_max)
+        .parameter("lambda", lambda)
+        .seed(seed));
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPool::new(n_workers);
+
+    let times = log_times(1.0, t_max);
+    let mut first = 0;
+    for (batch, runs) in batch_sizes(simulations as usize, n_batches).into_iter().enumerate() {
+        let times = times.clone();
+        let point = point.clone();
+        let replicas = first..first + runs;
+        first += runs;
+        execute(&pool, &tx, batch, move || {
+            eprintln!("Lambda: {}, Batch: {}", lambda, batch);
+            let mut sums = SpreadingSums::new(times.len());
+            for replica in replicas {
+                let mut sim = model.create_with_seed(&point, replica_seed(seed, &point, replica)).expect("parameters were validated");
+                sums.add_run(sim.as_mut(), &times);
             }
-            let end_nodes_avg = end_nodes_sum as f64 / simulations as f64;
-            let success_avg = success as f64 / simulations as f64;
-            tx.send((lambda, success_avg, end_nodes_avg)).unwrap();
+            sums
         });
     }
     pool.join();
     drop(tx);
-    for (lambda, success, end_nodes_average) in rx {
-        simulation_results.push(SimulationResult{lambda, success_average: success, end_nodes_average});
+    //a failed batch only costs statistics, the fit uses the remaining ones
+    let mut batches: Vec<SpreadingSums> = Vec::new();
+    for (batch, result) in rx {
+        match result {
+            Ok(sums) => batches.push(sums),
+            Err(message) => sweep.fail(format!("Lambda: {}, Batch: {}", lambda, batch), FailureReason::Panicked(message)),
+        }
+    }
+    if batches.is_empty() {
+        return Err(SweepError::Incomplete(sweep.failures));
     }
 
+    let (points, fit) = analysis::spreading::analyse(&batches, &times);
+    sweep.results = points;
+    Ok((sweep, fit))
+}
+
 
-    simulation_results.sort_by(|a, b| a.lambda.partial_cmp(&b.lambda).unwrap());
 
-    return simulation_results;
+//step size is 0.01 for lambda and 0.1 for alpha, alpha is ignored for Topology::OneD
+fn run_theory(approximation: Approximation,
+              topology: Topology,
+              lambda_range: (f64, f64),
+              alpha_range: (f64, f64)
+              ) -> Vec<TheoryResult> {
+    let lower_bound = (lambda_range.0 * 100.0) as i32;
+    let upper_bound = (lambda_range.1 * 100.0) as i32;
+
+    let alphas: Vec<Option<f64>> = match topology {
+        Topology::OneD => vec![None],
+        _ => {
+            let lower_bound_alpha = (alpha_range.0 * 10.0) as i32;
+            let upper_bound_alpha = (alpha_range.1 * 10.0) as i32;
+            (lower_bound_alpha..=upper_bound_alpha).map(|a| Some(a as f64 / 10.0)).collect()
+        }
+    };
+
+    let mut results = Vec::new();
+    for lambda in lower_bound..=upper_bound {
+        let lambda = lambda as f64 / 100.0;
+        for &alpha in &alphas {
+            let a = alpha.unwrap_or(1.0);
+            results.push(TheoryResult {
+                lambda,
+                alpha,
+                approximation,
+                density: stationary_density(approximation, topology, lambda, a),
+                threshold: threshold(approximation, topology, a),
+            });
+        }
+    }
+    results
 }
 
 
-fn run_simulation_alpha(simulations: i32,
+
+//step size is 0.01, survival is sampled at logarithmically spaced times up to t_max
+fn run_master_equation(l: usize,
+                       dimensions: usize,
+                       alpha: f64,
+                       t_max: f64,
+                       lambda_range: (f64, f64),
+                       n_workers: usize
+                       ) -> Result<Sweep<MasterResult>, SweepError> {
+    T_MAX.check(t_max)?;
+    DIMENSIONS.check(dimensions as f64)?;
+    ALPHA.check(alpha)?;
+    //the largest side length whose lattice still has at most MAX_SITES sites
+    let max_l = (1..).take_while(|&l: &usize| l.pow(dimensions as u32) <= MAX_SITES).last().unwrap_or(1);
+    Parameter { max: max_l as f64, ..L }.check(l as f64)?;
+    let mut sweep = Sweep::new(Manifest::new("run_master_equation")
+        .parameter("l", l)
+        .parameter("dimensions", dimensions)
+        .parameter("alpha", alpha)
+        .parameter("t_max", t_max)
+        .parameter("lambda_range", lambda_range));
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPool::new(n_workers);
+
+    let lower_bound = (lambda_range.0 * 100.0) as i32;
+    let upper_bound = (lambda_range.1 * 100.0) as i32;
+
+    for lambda in lower_bound..=upper_bound {
+        let lambda = lambda as f64 / 100.0;
+        if let Err(error) = LAMBDA.check(lambda) {
+            sweep.fail(format!("L: {}, Lambda: {}", l, lambda), FailureReason::InvalidParameters(error));
+            continue;
+        }
+        execute(&pool, &tx, lambda, move || {
+            eprintln!("L: {}, Lambda: {}", l, lambda);
+            let exact = MasterEquation::new(l, dimensions, lambda, alpha).expect("lattice size was validated");
+            let times = log_times(0.1, t_max);
+            let survival = exact.survival(exact.all_infected(), &times);
+            let qsd = exact.quasi_stationary();
+            MasterResult {
+                l,
+                lambda,
+                mean_absorption_time: exact.mean_absorption_time(exact.all_infected()),
+                decay_rate: qsd.as_ref().map(|q| q.decay_rate),
+                density: qsd.as_ref().map(|q| q.density()),
+                survival: times.into_iter().zip(survival).collect(),
+            }
+        });
+    }
+    pool.join();
+    drop(tx);
+    for (lambda, result) in rx {
+        match result {
+            Ok(result) => sweep.results.push(result),
+            Err(message) => sweep.fail(format!("L: {}, Lambda: {}", l, lambda), FailureReason::Panicked(message)),
+        }
+    }
+
+    sweep.results.sort_by(|a, b| a.lambda.partial_cmp(&b.lambda).unwrap());
+    Ok(sweep)
+}
+
+
+
+//step size is 0.01, the front is sampled every dt.
+//The model is looked up in the registry, alpha is needed by the models that take one
+#[allow(clippy::too_many_arguments)]
+fn run_front_simulation(model: &str,
+                        alpha: Option<f64>,
+                        simulations: i32,
                         t_max: f64,
+                        dt: f64,
                         lambda_range: (f64, f64),
-                        alpha_range: (f64, f64),
-                        sim_alpha_type: SimAlphaType
-                        ) -> Vec<SimulationResultAlpha>{
-    let mut simulation_results = Vec::new();
-    let n_workers = 12;
+                        seed: u64,
+                        n_workers: usize
+                        ) -> Result<Sweep<FrontResult>, SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    DT.check(dt)?;
+    let mut sweep = Sweep::new(Manifest::new("run_front_simulation")
+        .model(model.name)
+        .parameter("alpha", alpha)
+        .parameter("simulations", simulations)
+        .parameter("t_max", t_max)
+        .parameter("dt", dt)
+        .parameter("lambda_range", lambda_range)
+        .seed(seed));
     let (tx, rx) = mpsc::channel();
     let pool = ThreadPool::new(n_workers);
 
     let lower_bound = (lambda_range.0 * 100.0) as i32;
     let upper_bound = (lambda_range.1 * 100.0) as i32;
 
+    for lambda in lower_bound..=upper_bound {
+        let lambda = lambda as f64 / 100.0;
+        let point: Vec<f64> = std::iter::once(lambda).chain(alpha).collect();
+        if let Err(error) = model.validate(&point) {
+            sweep.fail(describe(model, &point), FailureReason::InvalidParameters(error));
+            continue;
+        }
+
+        execute(&pool, &tx, lambda, move || {
+            eprintln!("Lambda: {}", lambda);
+            let replica = Cell::new(0);
+            let new_sim = || {
+                replica.set(replica.get() + 1);
+                model.create_with_seed(&point, replica_seed(seed, &point, replica.get() - 1)).expect("grid point was validated")
+            };
+            let estimate = estimate_velocity(new_sim, simulations, t_max, dt, model.dimensions);
+            FrontResult {
+                lambda,
+                surviving: estimate.surviving,
+                velocity: estimate.velocity,
+                velocity_std_err: estimate.velocity_std_err,
+            }
+        });
+    }
+    pool.join();
+    drop(tx);
+    for (lambda, result) in rx {
+        match result {
+            Ok(result) => sweep.results.push(result),
+            Err(message) => sweep.fail(format!("Lambda: {}", lambda), FailureReason::Panicked(message)),
+        }
+    }
+
+    sweep.results.sort_by(|a, b| a.lambda.partial_cmp(&b.lambda).unwrap());
+
+    Ok(sweep)
+}
+
+
+
+//alpha step size is 0.1, radii are divided by t_max
+#[allow(clippy::too_many_arguments)]
+fn run_shape_simulation(simulations: i32,
+                        t_max: f64,
+                        lambda: f64,
+                        alpha_range: (f64, f64),
+                        n_angles: usize,
+                        region: Region,
+                        seed: u64,
+                        n_workers: usize
+                        ) -> Result<Sweep<ShapeResult>, SweepError> {
+    T_MAX.check(t_max)?;
+    N_ANGLES.check(n_angles as f64)?;
+    let mut sweep = Sweep::new(Manifest::new("run_shape_simulation")
+        .parameter("simulations", simulations)
+        .parameter("t_max", t_max)
+        .parameter("lambda", lambda)
+        .parameter("alpha_range", alpha_range)
+        .parameter("n_angles", n_angles)
+        .parameter("region", format!("{:?}", region))
+        .seed(seed));
+    let (tx, rx) = mpsc::channel();
+    let pool = ThreadPool::new(n_workers);
+
     let lower_bound_alpha = (alpha_range.0 * 10.0) as i32;
     let upper_bound_alpha = (alpha_range.1 * 10.0) as i32;
 
-    let simulations = simulations;
-    for lambda in lower_bound..=upper_bound {
-        let tx = tx.clone();
-        for alpha in lower_bound_alpha..=upper_bound_alpha {
-            let tx = tx.clone();
-            pool.execute(move || {
-                let lambda = lambda as f64 / 100.0;
-                let alpha = alpha as f64 / 10.0;
-                let mut success = 0;
-                let mut end_nodes_sum = 0;
-                println!("Lambda: {}, Alpha: {}", lambda, alpha);
-                for _i in 0..simulations {
-                    let mut sim = create_sim(sim_alpha_type, lambda, alpha);
-                    let result = sim.run(t_max);
-                    if result {
-                        success += 1;
-                    }
-                    end_nodes_sum += sim.get_number_of_infected_nodes();
-                }
-                let end_nodes_avg = end_nodes_sum as f64 / simulations as f64;
-                let success_avg = success as f64 / simulations as f64;
-                tx.send((lambda, alpha, success_avg, end_nodes_avg)).unwrap();
-            });
-        }
+    for alpha in lower_bound_alpha..=upper_bound_alpha {
+        let alpha = alpha as f64 / 10.0;
+        let params = match sim2d::ModelParams::new(lambda, alpha) {
+            Ok(params) => params,
+            Err(error) => {
+                sweep.fail(format!("Lambda: {}, Alpha: {}", lambda, alpha), FailureReason::InvalidParameters(error));
+                continue;
+            }
+        };
+        execute(&pool, &tx, alpha, move || {
+            eprintln!("Lambda: {}, Alpha: {}", lambda, alpha);
+            let seeds = (0..simulations.max(0) as usize).map(|replica| replica_seed(seed, &[lambda, alpha], replica));
+            let estimate = estimate_shape(params, seeds, t_max, n_angles, region);
+            estimate.points.into_iter().map(|point| ShapeResult {
+                lambda,
+                alpha,
+                surviving: estimate.surviving,
+                angle: point.angle,
+                radius: point.radius,
+                radius_std_err: point.radius_std_err,
+            }).collect::<Vec<_>>()
+        });
     }
     pool.join();
     drop(tx);
-    for (lambda, alpha, success, end_nodes_average) in rx {
-        simulation_results.push(SimulationResultAlpha{lambda, alpha, success_average: success, end_nodes_average});
+    for (alpha, result) in rx {
+        match result {
+            Ok(points) => sweep.results.extend(points),
+            Err(message) => sweep.fail(format!("Lambda: {}, Alpha: {}", lambda, alpha), FailureReason::Panicked(message)),
+        }
     }
 
-    //sort by alpha and lambda
-    simulation_results.sort_by(|a, b| {
-        if a.lambda == b.lambda {
-            a.alpha.partial_cmp(&b.alpha).unwrap()
+    //sort by alpha and angle
+    sweep.results.sort_by(|a, b| {
+        if a.alpha == b.alpha {
+            a.angle.partial_cmp(&b.angle).unwrap()
         } else {
-            a.lambda.partial_cmp(&b.lambda).unwrap()
+            a.alpha.partial_cmp(&b.alpha).unwrap()
         }
     });
-    return simulation_results;
+    Ok(sweep)
 }
 
+//a single run of a 1D model from the registry, its infected sites are sampled every dt
+fn run_spacetime(model: &str,
+                 alpha: Option<f64>,
+                 lambda: f64,
+                 t_max: f64,
+                 dt: f64,
+                 seed: u64
+                 ) -> Result<(SpaceTime, Manifest), SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    if model.dimensions != 1 {
+        return Err(SweepError::UnknownModel(format!("space-time diagrams need a 1D model but {} is {}D", model.name, model.dimensions)));
+    }
+    T_MAX.check(t_max)?;
+    DT.check(dt)?;
+    let point: Vec<f64> = std::iter::once(lambda).chain(alpha).collect();
+    let mut sim = model.create_with_seed(&point, seed)?;
+    let manifest = Manifest::new("run_spacetime")
+        .model(model.name)
+        .seed(seed)
+        .parameter("alpha", alpha)
+        .parameter("lambda", lambda)
+        .parameter("t_max", t_max)
+        .parameter("dt", dt);
+    Ok((SpaceTime::record(sim.as_mut(), t_max, dt), manifest))
+}
 
+//a single sim2d run, the infected and ever infected sites are sampled every dt
+fn run_snapshots(lambda: f64,
+                 alpha: f64,
+                 t_max: f64,
+                 dt: f64,
+                 seed: u64
+                 ) -> Result<(Vec<Frame>, Manifest), SweepError> {
+    T_MAX.check(t_max)?;
+    DT.check(dt)?;
+    let mut sim = sim2d::Simulation::with_seed(sim2d::ModelParams::new(lambda, alpha)?, seed);
+    let manifest = Manifest::new("run_snapshots")
+        .model("sim2d")
+        .seed(seed)
+        .parameter("lambda", lambda)
+        .parameter("alpha", alpha)
+        .parameter("t_max", t_max)
+        .parameter("dt", dt);
+    Ok((Frame::record(&mut sim, t_max, dt), manifest))
+}
 
+//a single run, its snapshot is continued by resume_run
+fn run_single(model: &str,
+              alpha: Option<f64>,
+              lambda: f64,
+              t_max: f64,
+              seed: u64
+              ) -> Result<(Box<dyn Sim>, bool, Manifest), SweepError> {
+    let model = registry::find(model).map_err(SweepError::UnknownModel)?;
+    T_MAX.check(t_max)?;
+    let point: Vec<f64> = std::iter::once(lambda).chain(alpha).collect();
+    let mut sim = model.create_with_seed(&point, seed)?;
+    let died = sim.run(t_max);
+    let manifest = Manifest::new("run_single")
+        .model(model.name)
+        .seed(seed)
+        .parameter("alpha", alpha)
+        .parameter("lambda", lambda)
+        .parameter("t_max", t_max);
+    Ok((sim, died, manifest))
+}
 
+//a run saved at or after t_max is returned as it is
+fn resume_run(snapshot: Snapshot, t_max: f64) -> Result<(Box<dyn Sim>, bool), SweepError> {
+    T_MAX.check(t_max)?;
+    let mut sim = snapshot.restore()?;
+    let died = sim.run(t_max);
+    Ok((sim, died))
+}
 
-
+//progress and summaries go to stderr, stdout only carries the results written to `-`
 fn print_results<S: Display>(results: &Vec<S>) {
     for result in results {
-        println!("{}", result);
+        eprintln!("{}", result);
     }
 }
 
-fn print_results_to_csv_file<S:WriteToCsv>(results: &Vec<S>, file_name: &str) {
-    let mut wtr = csv::Writer::from_path(file_name).unwrap();
-    S::write_header_to_csv(&mut wtr);
+fn write_records<S: WriteRecords>(results: &Vec<S>, wtr: &mut dyn RecordWriter) -> std::io::Result<()> {
+    S::write_header_for(results, wtr)?;
     for result in results {
-        result.write_to_csv(&mut wtr);
+        result.write_records(wtr)?;
     }
-    wtr.flush().unwrap();
+    wtr.flush()
 }
 
+//the format follows the extension of the file name, see output::Format, "-" writes to stdout.
+//A file gets the manifest next to it, a .sqlite or .db store keeps it with the results
+fn write_results<S: WriteRecords>(results: &Vec<S>, manifest: &Manifest, output: &str) -> Result<(), Box<dyn Error>> {
+    if store::is_database(output) {
+        let mut store = Store::open(output)?;
+        write_records(results, &mut store.add_run(S::KIND, &manifest.finished())?)?;
+    } else {
+        write_records(results, output::create(output)?.as_mut())?;
+        manifest.write_next_to(output)?;
+    }
+    Ok(())
+}
 
-use simulation::rbtree::RBTree;
+
+//combines result files of run_simulation or run_simulation_alpha, rows of the same grid point
+//are merged by adding their sums and every average and interval is derived from the merged sums.
+//Extinction times are written to their own files and are not merged here.
+//The inputs may be CSV, JSON Lines or MessagePack files, results in a store can't be merged.
+fn merge_result_files(inputs: &[String], output: &str) -> Result<(), Box<dyn Error>> {
+    let manifest = Manifest::new("merge").parameter("inputs", inputs);
+    let mut has_alpha = None;
+    let mut merged: Vec<((f64, f64), RunSums)> = Vec::new();
+    for input in inputs {
+        if store::is_database(input) {
+            return Err(format!("{}: only CSV, JSON Lines and MessagePack result files can be merged, not stores", input).into());
+        }
+        let table = output::open(input).map_err(|e| format!("{}: {}", input, e))?;
+        let header = csv::StringRecord::from(table.header);
+        let alpha_column = header.iter().position(|h| h == "Alpha");
+        if *has_alpha.get_or_insert(alpha_column.is_some()) != alpha_column.is_some() {
+            return Err(format!("{}: only some of the files have an alpha column", input).into());
+        }
+        let lambda_column = header.iter().position(|h| h == "Lambda")
+            .ok_or_else(|| format!("{}: missing column Lambda", input))?;
+        for record in table.rows.into_iter().map(csv::StringRecord::from) {
+            let parse = |column: usize| -> Result<f64, String> {
+                record[column].parse().map_err(|_| format!("{}: {} is not a number", input, &record[column]))
+            };
+            let point = (parse(lambda_column)?, alpha_column.map(parse).transpose()?.unwrap_or(0.0));
+            let sums = RunSums::from_csv(&header, &record).map_err(|e| format!("{}: {}", input, e))?;
+            match merged.iter_mut().find(|(p, _)| *p == point) {
+                Some((_, existing)) => existing.merge(&sums),
+                None => merged.push((point, sums)),
+            }
+        }
+    }
+
+    //sort by lambda and alpha like the sweeps
+    merged.sort_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap());
+    if has_alpha == Some(true) {
+        let results: Vec<_> = merged.into_iter()
+            .map(|((lambda, alpha), sums)| SimulationResultAlpha::new(lambda, alpha, sums, ExtinctionTimes::default()))
+            .collect();
+        write_results(&results, &manifest, output)?;
+    } else {
+        let results: Vec<_> = merged.into_iter()
+            .map(|((lambda, _), sums)| SimulationResult::new(lambda, sums, ExtinctionTimes::default()))
+            .collect();
+        write_results(&results, &manifest, output)?;
+    }
+    Ok(())
+}
+
+
+fn write_sweep<S: WriteRecords>(sweep: &Sweep<S>, output: &str) -> Result<(), Box<dyn Error>> {
+    write_results(&sweep.results, &sweep.finished_manifest(), output)
+}
+
+//a resumed sweep without a seed continues with the one of its checkpoint, a new sweep draws one
+fn sweep_seed(seed: Option<u64>, checkpoint: Option<&str>) -> Result<u64, CheckpointError> {
+    if let Some(seed) = seed {
+        return Ok(seed);
+    }
+    let saved = match checkpoint {
+        Some(path) => checkpoint::saved_seed(path)?,
+        None => None,
+    };
+    Ok(saved.unwrap_or_else(rand::random))
+}
+
+//writes the results of the grid points that succeeded before failed ones are reported
+fn run_experiment(experiment: Experiment, n_workers: usize) -> Result<(), Box<dyn Error>> {
+    match experiment {
+        Experiment::Simulation { model, simulations, t_max, lambda, checkpoint, replicas, seed, output, extinction_output } => {
+            let seed = sweep_seed(seed, checkpoint.as_deref())?;
+            let sweep = run_simulation(&model, simulations, t_max, lambda, checkpoint.as_deref(), replicas.as_deref(), seed, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            if let Some(extinction_output) = extinction_output {
+                let extinction: Vec<_> = sweep.results.iter().map(|r| r.extinction_result()).collect();
+                write_results(&extinction, &sweep.finished_manifest(), &extinction_output)?;
+            }
+            sweep.finish()?;
+        }
+        Experiment::SimulationAlpha { model, simulations, t_max, lambda, alpha, checkpoint, replicas, seed, output, extinction_output } => {
+            let seed = sweep_seed(seed, checkpoint.as_deref())?;
+            let sweep = run_simulation_alpha(&model, simulations, t_max, lambda, alpha, checkpoint.as_deref(), replicas.as_deref(), seed, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            if let Some(extinction_output) = extinction_output {
+                let extinction: Vec<_> = sweep.results.iter().map(|r| r.extinction_result()).collect();
+                write_results(&extinction, &sweep.finished_manifest(), &extinction_output)?;
+            }
+            sweep.finish()?;
+        }
+        Experiment::Adaptive { model, batch_size, max_simulations, target_width, t_max, lambda, replicas, seed, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let sweep = run_simulation_adaptive(&model, batch_size, max_simulations, target_width, t_max, lambda, replicas.as_deref(), seed, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            sweep.finish()?;
+        }
+        Experiment::Fss { simulations, t_relax, t_max, sizes, dimensions, alpha, lambda, seed, output, fit_output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let mut sweep = run_fss_simulation(simulations, t_relax, t_max, &sizes, dimensions, alpha, lambda, seed, n_workers)?;
+            let fit = analysis::fss::analyse(&mut sweep.results, dimensions);
+            eprintln!("{}", fit);
+            write_sweep(&sweep, &output)?;
+            if let Some(fit_output) = fit_output {
+                write_results(&vec![fit], &sweep.finished_manifest(), &fit_output)?;
+            }
+            sweep.finish()?;
+        }
+        Experiment::Spreading { model, alpha, simulations, t_max, lambda, seed, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let (sweep, fit) = run_spreading_simulation(&model, alpha, simulations, t_max, lambda, seed, n_workers)?;
+            eprintln!("{}", fit);
+            write_sweep(&sweep, &output)?;
+            sweep.finish()?;
+        }
+        Experiment::Theory { approximation, topology, lambda, alpha, output } => {
+            let manifest = Manifest::new("run_theory")
+                .parameter("approximation", approximation.name())
+                .parameter("topology", format!("{:?}", topology))
+                .parameter("lambda_range", lambda)
+                .parameter("alpha_range", alpha);
+            let results = run_theory(approximation, topology, lambda, alpha);
+            print_results(&results);
+            write_results(&results, &manifest, &output)?;
+        }
+        Experiment::Master { l, dimensions, alpha, t_max, lambda, output } => {
+            let sweep = run_master_equation(l, dimensions, alpha, t_max, lambda, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            sweep.finish()?;
+        }
+        Experiment::Front { model, alpha, simulations, t_max, dt, lambda, seed, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let sweep = run_front_simulation(&model, alpha, simulations, t_max, dt, lambda, seed, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            sweep.finish()?;
+        }
+        Experiment::Shape { simulations, t_max, lambda, alpha, n_angles, region, seed, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let sweep = run_shape_simulation(simulations, t_max, lambda, alpha, n_angles, region, seed, n_workers)?;
+            print_results(&sweep.results);
+            write_sweep(&sweep, &output)?;
+            sweep.finish()?;
+        }
+        Experiment::Spacetime { model, alpha, lambda, t_max, dt, seed, scale, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let (spacetime, manifest) = run_spacetime(&model, alpha, lambda, t_max, dt, seed)?;
+            let (min, max) = spacetime.extent();
+            eprintln!("Samples: {}, Sites: [{}, {}]", spacetime.rows.len(), min, max);
+            spacetime.save(&output, scale)?;
+            manifest.parameter("scale", scale).write_next_to(&output)?;
+        }
+        Experiment::Run { model, alpha, lambda, t_max, seed, snapshot } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let (sim, died, manifest) = run_single(&model, alpha, lambda, t_max, seed)?;
+            eprintln!("T: {}, Infected: {}, Died: {}", sim.get_t(), sim.get_number_of_infected_nodes(), died);
+            sim.snapshot().save(&snapshot)?;
+            manifest.write_next_to(&snapshot)?;
+        }
+        Experiment::Resume { resume, t_max, snapshot } => {
+            let saved = Snapshot::load(&resume).map_err(|e| format!("could not load snapshot {}, {}", resume, e))?;
+            let (sim, died) = resume_run(saved, t_max)?;
+            eprintln!("T: {}, Infected: {}, Died: {}", sim.get_t(), sim.get_number_of_infected_nodes(), died);
+            if let Some(snapshot) = snapshot {
+                sim.snapshot().save(&snapshot)?;
+                Manifest::new("resume_run").parameter("resume", &resume).parameter("t_max", t_max).write_next_to(&snapshot)?;
+            }
+        }
+        Experiment::Snapshots { lambda, alpha, t_max, dt, seed, viewport, size, output } => {
+            let seed = seed.unwrap_or_else(rand::random);
+            let (frames, manifest) = run_snapshots(lambda, alpha, t_max, dt, seed)?;
+            let last = frames.last().expect("t = 0 is always sampled");
+            eprintln!("Frames: {}, Infected: {}, Ever infected: {}", frames.len(), last.infected.len(), last.ever_infected.len());
+            frames::save(&frames, viewport, size, &output)?;
+            manifest.parameter("viewport", format!("{:?}", viewport)).parameter("size", size).write_next_to(&output)?;
+        }
+    }
+    Ok(())
+}
 
 
 #[derive(PartialEq, Eq, PartialOrd, Ord)]
@@ -208,9 +1505,199 @@ pub enum State {
 }
 
 
-fn main() {
+//`run <config>...` executes the experiments of TOML or JSON config files, see `config::Config`.
+//A failed experiment is reported and the batch continues with the next one,
+//an interrupted sweep continues from its checkpoint when it is started again.
+//`merge <output> <input>...` combines result files of sweeps over the same grid.
+//`models` lists the registered models with their parameters.
+fn main() -> Result<(), Box<dyn Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.get(1).map(String::as_str) {
+        Some("merge") if args.len() >= 4 => merge_result_files(&args[3..], &args[2]),
+        Some("models") => {
+            for model in registry::MODELS {
+                println!("{}", model);
+            }
+            Ok(())
+        }
+        Some("run") if args.len() >= 3 => {
+            //all files are read first so a typo in the last one doesn't surface after hours of sweeps
+            let configs = args[2..].iter()
+                .map(|path| Config::load(path).map_err(|e| format!("{}: {}", path, e)))
+                .collect::<Result<Vec<_>, _>>()?;
+            let mut failed = 0;
+            for config in configs {
+                for experiment in config.experiments {
+                    if let Err(error) = run_experiment(experiment, config.workers) {
+                        eprintln!("experiment failed, {}", error);
+                        failed += 1;
+                    }
+                }
+            }
+            if failed > 0 {
+                return Err(format!("{} experiments failed", failed).into());
+            }
+            Ok(())
+        }
+        _ => Err("usage: run <config.toml>... | merge <output> <input>... | models".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn invalid<T>(result: Result<T, SweepError>) -> bool {
+        matches!(result, Err(SweepError::InvalidParameter(_)))
+    }
+
+    #[test]
+    fn test_finite_lattices_validated() {
+        //5 x 5 sites are more than MAX_SITES, 4 x 4 are fine
+        assert!(invalid(run_master_equation(5, 2, 0.5, 1.0, (1.0, 1.0), 1)));
+        assert!(invalid(run_master_equation(3, 3, 0.5, 1.0, (1.0, 1.0), 1)));
+        assert_eq!(run_master_equation(4, 2, 0.5, 1.0, (1.0, 1.0), 1).unwrap().results.len(), 1);
+        assert!(invalid(run_fss_simulation(0, 1.0, 2.0, &[4], 1, 0.5, (1.0, 1.0), 0, 1)));
+        assert!(invalid(run_fss_simulation(10, 1.0, 2.0, &[4], 3, 0.5, (1.0, 1.0), 0, 1)));
+        assert!(invalid(run_fss_simulation(10, 1.0, 2.0, &[0, 4], 1, 0.5, (1.0, 1.0), 0, 1)));
+    }
+
+    fn header<S: WriteRecords>(results: &Vec<S>) -> Vec<String> {
+        let mut out = Vec::new();
+        write_records(results, output::writer(output::Format::Csv, &mut out).as_mut()).unwrap();
+        csv::Reader::from_reader(out.as_slice()).headers().unwrap().iter().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_theory_columns() {
+        let simulation = header::<SimulationResult>(&Vec::new());
+        let simulation_alpha = header::<SimulationResultAlpha>(&Vec::new());
+        let one_d = header(&run_theory(Approximation::Pair, Topology::OneD, (1.5, 1.6), (1.0, 1.0)));
+        let two_d = header(&run_theory(Approximation::Pair, Topology::TwoD, (1.5, 1.6), (0.5, 0.5)));
+        assert_eq!(one_d[..2], simulation[..2]);
+        assert_eq!(two_d[..3], simulation_alpha[..3]);
+    }
+
+    #[test]
+    fn test_adaptive_stops() {
+        //at lambda = 0 every run dies, after one batch of 50 the interval is narrower than 0.1
+        let sweep = run_simulation_adaptive("sim1d", 50, 1000, 0.1, 10.0, (0.0, 0.0), None, 1, 2).unwrap();
+        assert_eq!(sweep.results.len(), 1);
+        assert_eq!(sweep.results[0].sums.runs, 50);
+
+        //the interval never gets that narrow, so the sweep stops at max_simulations
+        let sweep = run_simulation_adaptive("sim1d", 50, 200, 1e-6, 10.0, (0.0, 0.01), None, 1, 2).unwrap();
+        assert!(sweep.results.iter().all(|r| r.sums.runs == 200));
+
+        for (batch_size, max_simulations, target_width) in [(0, 100, 0.1), (50, 20, 0.1), (50, 100, 0.0), (50, 100, f64::NAN)] {
+            assert!(invalid(run_simulation_adaptive("sim1d", batch_size, max_simulations, target_width, 10.0, (0.0, 0.0), None, 1, 2)));
+        }
+    }
+
+    #[test]
+    fn test_resume() {
+        let (paused, _, _) = run_single("sim2d", Some(0.7), 2.5, 5.0, 3).unwrap();
+        let path = std::env::temp_dir().join(format!("resume_{}.msgpack", std::process::id()));
+        paused.snapshot().save(&path).unwrap();
+        let (resumed, died) = resume_run(Snapshot::load(&path).unwrap(), 10.0).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let (uninterrupted, uninterrupted_died, _) = run_single("sim2d", Some(0.7), 2.5, 10.0, 3).unwrap();
+        assert_eq!(died, uninterrupted_died);
+        assert_eq!(resumed.get_t(), uninterrupted.get_t());
+        let sorted = |sim: &dyn Sim| {
+            let mut sites = sim.get_infected_sites();
+            sites.sort();
+            sites
+        };
+        assert_eq!(sorted(resumed.as_ref()), sorted(uninterrupted.as_ref()));
+        assert!(invalid(resume_run(uninterrupted.snapshot(), -1.0)));
+    }
+
+    #[test]
+    fn test_merge_formats() {
+        let dir = std::env::temp_dir();
+        let path = |name: &str| dir.join(format!("merge_{}_{}", std::process::id(), name)).to_string_lossy().into_owned();
+        let inputs = [path("a.csv"), path("b.jsonl"), path("c.msgpack")];
+        for (i, input) in inputs.iter().enumerate() {
+            let sweep = run_simulation("sim1d", 10, 5.0, (1.0, 1.1), None, None, i as u64, 2).unwrap();
+            write_sweep(&sweep, input).unwrap();
+        }
+        let output = path("merged.csv");
+        merge_result_files(&inputs, &output).unwrap();
+        let table = output::open(&output).unwrap();
+        let runs = table.header.iter().position(|h| h == "Runs").unwrap();
+        assert_eq!(table.rows.len(), 11);
+        assert!(table.rows.iter().all(|row| row[runs] == "30"));
+        for file in inputs.iter().chain([&output]) {
+            std::fs::remove_file(file).unwrap();
+            std::fs::remove_file(Manifest::path_for(file)).unwrap();
+        }
 
-    let results = run_simulation(1000, 1000.0, (1.86, 2.0));
-    print_results(&results);
-    print_results_to_csv_file(&results, "results_rb_continue.csv");
+        assert!(merge_result_files(&[path("results.sqlite")], &output).unwrap_err().to_string().contains("stores"));
+    }
+
+    #[test]
+    fn test_checkpoint_resume() {
+        let path = std::env::temp_dir().join(format!("sweep_checkpoint_{}.json", std::process::id()));
+        let checkpoint = path.to_str();
+        let _ = std::fs::remove_file(&path);
+        //a finished sweep of 150 replicas extended to 250 runs every replica exactly once
+        run_simulation("sim1d", 150, 5.0, (1.5, 1.5), checkpoint, None, 11, 2).unwrap();
+        assert_eq!(sweep_seed(None, checkpoint).unwrap(), 11);
+        assert!(matches!(run_simulation("sim1d", 250, 5.0, (1.5, 1.5), checkpoint, None, 12, 2), Err(SweepError::Checkpoint(_))));
+        let resumed = run_simulation("sim1d", 250, 5.0, (1.5, 1.5), checkpoint, None, 11, 2).unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        let uninterrupted = run_simulation("sim1d", 250, 5.0, (1.5, 1.5), None, None, 11, 2).unwrap();
+        let (a, b) = (resumed.results[0].sums, uninterrupted.results[0].sums);
+        assert_eq!((a.runs, a.died, a.end_nodes_sum, a.infections_sum), (b.runs, b.died, b.end_nodes_sum, b.infections_sum));
+    }
+
+    #[test]
+    fn test_checkpoint_partial_resume() {
+        let path = std::env::temp_dir().join(format!("sweep_partial_{}.json", std::process::id()));
+        let replicas = std::env::temp_dir().join(format!("sweep_partial_replicas_{}.csv", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        //a sweep interrupted after two batches which finished out of order
+        let model = registry::find("sim1d").unwrap();
+        let point = [1.5];
+        let mut checkpoint = Checkpoint::open(&path, model.name, 5.0, 11).unwrap();
+        for batch in [0..30, 100..120] {
+            let mut tally = Tally::new(5.0);
+            for replica in batch.clone() {
+                run_replica(model, &point, replica, 11, 5.0, &mut tally, None);
+            }
+            checkpoint.add(&point, batch, tally).unwrap();
+        }
+
+        let resumed = run_simulation("sim1d", 250, 5.0, (1.5, 1.5), path.to_str(), replicas.to_str(), 11, 2).unwrap();
+        let table = output::open(replicas.to_str().unwrap()).unwrap();
+        let column = table.header.iter().position(|h| h == "Replica").unwrap();
+        let mut run: Vec<usize> = table.rows.iter().map(|row| row[column].parse().unwrap()).collect();
+        run.sort_unstable();
+        assert_eq!(run, (30..100).chain(120..250).collect::<Vec<_>>());
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(&replicas).unwrap();
+        std::fs::remove_file(Manifest::path_for(&replicas)).ok();
+
+        let uninterrupted = run_simulation("sim1d", 250, 5.0, (1.5, 1.5), None, None, 11, 2).unwrap();
+        let (a, b) = (resumed.results[0].sums, uninterrupted.results[0].sums);
+        assert_eq!((a.runs, a.died, a.end_nodes_sum, a.infections_sum), (b.runs, b.died, b.end_nodes_sum, b.infections_sum));
+    }
+
+    #[test]
+    fn test_seeded_sweeps() {
+        let fss = || run_fss_simulation(3, 1.0, 4.0, &[4], 1, 1.0, (1.6, 1.7), 5, 2).unwrap().results;
+        let (a, b) = (fss(), fss());
+        assert!(a.iter().zip(&b).all(|(a, b)| a.order_parameter.to_bits() == b.order_parameter.to_bits() && a.susceptibility.to_bits() == b.susceptibility.to_bits()));
+        let spreading = || run_spreading_simulation("sim1d", None, 40, 20.0, 1.8, 5, 2).unwrap().0.results;
+        let (a, b) = (spreading(), spreading());
+        assert!(a.iter().zip(&b).all(|(a, b)| a.infected == b.infected && a.survival == b.survival));
+        let front = || run_front_simulation("sim1d_rb", None, 4, 20.0, 1.0, (3.0, 3.0), 5, 2).unwrap().results;
+        assert_eq!(front()[0].velocity.to_bits(), front()[0].velocity.to_bits());
+        let shape = || run_shape_simulation(4, 10.0, 3.0, (0.5, 0.5), 8, Region::Infected, 5, 2).unwrap().results;
+        let (a, b) = (shape(), shape());
+        assert!(a.iter().zip(&b).all(|(a, b)| a.radius.to_bits() == b.radius.to_bits()));
+    }
 }
@@ -0,0 +1,134 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::output;
+
+/// Everything needed to trace a result file back to the run which produced it,
+/// written as JSON next to the file.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Manifest {
+    /// Name of the sweep function.
+    pub sweep: String,
+    pub model: Option<String>,
+    pub parameters: BTreeMap<String, serde_json::Value>,
+    /// Seed every replica seed is derived from, see `sweep::replica_seed`.
+    /// Missing for the sweeps without randomness, the master equation and the theory.
+    pub seed: Option<u64>,
+    /// Commit the binary was built from, see `build.rs`.
+    pub git_commit: Option<String>,
+    /// Whether tracked files of the source tree had uncommitted changes when it was built.
+    pub git_dirty: Option<bool>,
+    pub host: Option<String>,
+    /// Seconds since the unix epoch.
+    pub started: u64,
+    /// Seconds from the start of the sweep until the manifest was written.
+    pub wall_time: f64,
+    /// Grid points without results, see `sweep::Failure`.
+    pub failures: Vec<String>,
+    #[serde(skip)]
+    clock: Option<Instant>,
+}
+
+impl Manifest {
+    /// Starts the wall clock of the sweep.
+    pub fn new(sweep: &str) -> Self {
+        Manifest {
+            sweep: sweep.to_string(),
+            model: None,
+            parameters: BTreeMap::new(),
+            seed: None,
+            git_commit: Some(env!("GIT_COMMIT")).filter(|commit| !commit.is_empty()).map(String::from),
+            git_dirty: env!("GIT_DIRTY").parse().ok(),
+            host: host(),
+            started: SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs()),
+            wall_time: 0.0,
+            failures: Vec::new(),
+            clock: Some(Instant::now()),
+        }
+    }
+
+    pub fn model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    pub fn parameter<T: Serialize>(mut self, name: &str, value: T) -> Self {
+        let value = serde_json::to_value(value).expect("parameters are plain values");
+        self.parameters.insert(name.to_string(), value);
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// `results.csv` gets `results.manifest.json`.
+    pub fn path_for<P: AsRef<Path>>(data: P) -> PathBuf {
+        data.as_ref().with_extension("manifest.json")
+    }
+
+    /// A copy with the wall time taken now.
+    pub fn finished(&self) -> Manifest {
+        let mut manifest = self.clone();
+        if let Some(clock) = self.clock {
+            manifest.wall_time = clock.elapsed().as_secs_f64();
+        }
+        manifest
+    }
+
+    /// Writes the manifest next to the data file, the wall time is taken now.
+    /// Data written to stdout gets no manifest.
+    pub fn write_next_to<P: AsRef<Path>>(&self, data: P) -> io::Result<()> {
+        if data.as_ref() == Path::new(output::STDOUT) {
+            return Ok(());
+        }
+        let json = serde_json::to_string_pretty(&self.finished()).map_err(io::Error::other)?;
+        fs::write(Self::path_for(data), json)
+    }
+}
+
+fn host() -> Option<String> {
+    std::env::var("HOSTNAME").ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manifest() {
+        let data = std::env::temp_dir().join(format!("manifest_{}.csv", std::process::id()));
+        let manifest = Manifest::new("run_simulation")
+            .model("sim1d")
+            .parameter("simulations", 1000)
+            .parameter("lambda_range", (1.5, 2.0))
+            .seed(42);
+        manifest.write_next_to(&data).unwrap();
+
+        let path = Manifest::path_for(&data);
+        assert_eq!(path.extension().unwrap(), "json");
+        let written: Manifest = serde_json::from_str(&fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(written.model.as_deref(), Some("sim1d"));
+        assert_eq!(written.parameters["simulations"], 1000);
+        assert_eq!(written.parameters["lambda_range"], serde_json::json!([1.5, 2.0]));
+        assert_eq!(written.seed, Some(42));
+        //built outside a git checkout, e.g. from a source tarball, neither is known
+        match written.git_commit {
+            Some(commit) => {
+                assert_eq!(commit.len(), 40);
+                assert!(written.git_dirty.is_some());
+            }
+            None => assert!(written.git_dirty.is_none()),
+        }
+        fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,310 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+
+use serde::ser::{Serialize, SerializeMap, Serializer};
+use serde_json::Value;
+
+/// Path which writes to standard output instead of a file.
+pub const STDOUT: &str = "-";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    /// One JSON object per row keyed by the header.
+    JsonLines,
+    /// The header as an array of strings followed by one array per row, for large per-replica data.
+    MessagePack,
+}
+
+impl Format {
+    /// `.jsonl` is JSON Lines, `.msgpack` MessagePack and everything else including stdout CSV.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Format {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("jsonl") => Format::JsonLines,
+            Some("msgpack") => Format::MessagePack,
+            _ => Format::Csv,
+        }
+    }
+}
+
+/// Rows of named columns, the header is written once before the first record.
+/// Fields are passed as text like in a CSV file, the typed formats store fields which parse
+/// as numbers as numbers and empty fields as null.
+pub trait RecordWriter {
+    fn write_header(&mut self, header: &[&str]) -> io::Result<()>;
+    fn write_record(&mut self, record: &[String]) -> io::Result<()>;
+    fn flush(&mut self) -> io::Result<()>;
+}
+
+pub fn writer<'a, W: Write + 'a>(format: Format, out: W) -> Box<dyn RecordWriter + 'a> {
+    match format {
+        Format::Csv => Box::new(CsvWriter(csv::Writer::from_writer(out))),
+        Format::JsonLines => Box::new(JsonLinesWriter { out: BufWriter::new(out), header: Vec::new() }),
+        Format::MessagePack => Box::new(MessagePackWriter(BufWriter::new(out))),
+    }
+}
+
+/// Opens `path` in the format of its extension, `-` writes CSV to stdout.
+pub fn create(path: &str) -> io::Result<Box<dyn RecordWriter>> {
+    if path == STDOUT {
+        return Ok(writer(Format::Csv, io::stdout()));
+    }
+    Ok(writer(Format::from_path(path), File::create(path)?))
+}
+
+struct CsvWriter<W: Write>(csv::Writer<W>);
+
+impl<W: Write> RecordWriter for CsvWriter<W> {
+    fn write_header(&mut self, header: &[&str]) -> io::Result<()> {
+        Ok(self.0.write_record(header)?)
+    }
+    fn write_record(&mut self, record: &[String]) -> io::Result<()> {
+        Ok(self.0.write_record(record)?)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+struct JsonLinesWriter<W: Write> {
+    out: BufWriter<W>,
+    header: Vec<String>,
+}
+
+impl<W: Write> RecordWriter for JsonLinesWriter<W> {
+    fn write_header(&mut self, header: &[&str]) -> io::Result<()> {
+        self.header = header.iter().map(|h| h.to_string()).collect();
+        Ok(())
+    }
+    fn write_record(&mut self, record: &[String]) -> io::Result<()> {
+        serde_json::to_writer(&mut self.out, &Row { header: &self.header, record })?;
+        self.out.write_all(b"\n")
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+struct MessagePackWriter<W: Write>(BufWriter<W>);
+
+impl<W: Write> RecordWriter for MessagePackWriter<W> {
+    fn write_header(&mut self, header: &[&str]) -> io::Result<()> {
+        rmp_serde::encode::write(&mut self.0, header).map_err(io::Error::other)
+    }
+    fn write_record(&mut self, record: &[String]) -> io::Result<()> {
+        let fields: Vec<Field> = record.iter().map(|f| Field::parse(f)).collect();
+        rmp_serde::encode::write(&mut self.0, &fields).map_err(io::Error::other)
+    }
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// A text field typed by its contents, for the formats which store numbers as numbers.
+/// Integers beyond i64, such as seeds, stay exact as unsigned integers instead of becoming reals.
+pub enum Field<'a> {
+    Null,
+    Bool(bool),
+    Integer(i64),
+    Unsigned(u64),
+    Real(f64),
+    Text(&'a str),
+}
+
+impl<'a> Field<'a> {
+    pub fn parse(field: &'a str) -> Self {
+        match field {
+            "" => return Field::Null,
+            "true" => return Field::Bool(true),
+            "false" => return Field::Bool(false),
+            _ => {}
+        }
+        if let Ok(integer) = field.parse() {
+            return Field::Integer(integer);
+        }
+        if let Ok(unsigned) = field.parse() {
+            return Field::Unsigned(unsigned);
+        }
+        //JSON has no NaN or infinity, they stay text so they read back unchanged
+        match field.parse::<f64>() {
+            Ok(real) if real.is_finite() => Field::Real(real),
+            _ => Field::Text(field),
+        }
+    }
+}
+
+impl Serialize for Field<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match *self {
+            Field::Null => serializer.serialize_unit(),
+            Field::Bool(boolean) => serializer.serialize_bool(boolean),
+            Field::Integer(integer) => serializer.serialize_i64(integer),
+            Field::Unsigned(unsigned) => serializer.serialize_u64(unsigned),
+            Field::Real(real) => serializer.serialize_f64(real),
+            Field::Text(text) => serializer.serialize_str(text),
+        }
+    }
+}
+
+//rows with more fields than the header get their position as the key
+struct Row<'a> {
+    header: &'a [String],
+    record: &'a [String],
+}
+
+impl Serialize for Row<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.record.len()))?;
+        for (i, field) in self.record.iter().enumerate() {
+            match self.header.get(i) {
+                Some(name) => map.serialize_entry(name, &Field::parse(field))?,
+                None => map.serialize_entry(&i.to_string(), &Field::parse(field))?,
+            }
+        }
+        map.end()
+    }
+}
+
+/// Header and rows of a file written by a `RecordWriter`, with the fields as text again.
+pub struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Reads a table in any of the formats. JSON Lines rows get the keys of the first row as the
+/// header and missing keys as empty fields.
+pub fn read<R: BufRead>(format: Format, mut input: R) -> io::Result<Table> {
+    match format {
+        Format::Csv => {
+            let mut rdr = csv::Reader::from_reader(input);
+            let header = rdr.headers()?.iter().map(String::from).collect();
+            let rows = rdr.records()
+                .map(|record| Ok(record?.iter().map(String::from).collect()))
+                .collect::<io::Result<_>>()?;
+            Ok(Table { header, rows })
+        }
+        Format::JsonLines => {
+            let mut header: Option<Vec<String>> = None;
+            let mut rows = Vec::new();
+            for line in input.lines() {
+                let line = line?;
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let mut row: serde_json::Map<String, Value> = serde_json::from_str(&line)?;
+                let header = header.get_or_insert_with(|| row.keys().cloned().collect());
+                rows.push(header.iter().map(|name| row.remove(name).map_or_else(String::new, text)).collect());
+            }
+            Ok(Table { header: header.unwrap_or_default(), rows })
+        }
+        Format::MessagePack => {
+            let header: Vec<String> = rmp_serde::decode::from_read(&mut input).map_err(io::Error::other)?;
+            let mut rows = Vec::new();
+            while !input.fill_buf()?.is_empty() {
+                let row: Vec<Value> = rmp_serde::decode::from_read(&mut input).map_err(io::Error::other)?;
+                rows.push(row.into_iter().map(text).collect());
+            }
+            Ok(Table { header, rows })
+        }
+    }
+}
+
+/// Reads `path` in the format of its extension.
+pub fn open(path: &str) -> io::Result<Table> {
+    read(Format::from_path(path), BufReader::new(File::open(path)?))
+}
+
+//the inverse of Field::parse
+fn text(value: Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(text) => text,
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write(format: Format) -> Vec<u8> {
+        let mut out = Vec::new();
+        {
+            let mut wtr = writer(format, &mut out);
+            wtr.write_header(&["Lambda", "Runs", "Threshold", "Approximation"]).unwrap();
+            wtr.write_record(&["1.5".to_string(), "100".to_string(), String::new(), "pair".to_string()]).unwrap();
+            wtr.write_record(&["1.6".to_string(), "50".to_string(), "1.2".to_string(), "pair".to_string()]).unwrap();
+            wtr.flush().unwrap();
+        }
+        out
+    }
+
+    #[test]
+    fn test_formats() {
+        assert_eq!(String::from_utf8(write(Format::Csv)).unwrap(),
+                   "Lambda,Runs,Threshold,Approximation\n1.5,100,,pair\n1.6,50,1.2,pair\n");
+
+        let text = String::from_utf8(write(Format::JsonLines)).unwrap();
+        let rows: Vec<serde_json::Value> = text.lines().map(|l| serde_json::from_str(l).unwrap()).collect();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0], serde_json::json!({"Lambda": 1.5, "Runs": 100, "Threshold": null, "Approximation": "pair"}));
+        assert_eq!(rows[1]["Threshold"], 1.2);
+
+        let bytes = write(Format::MessagePack);
+        let mut reader = bytes.as_slice();
+        let header: Vec<String> = rmp_serde::decode::from_read(&mut reader).unwrap();
+        assert_eq!(header, ["Lambda", "Runs", "Threshold", "Approximation"]);
+        let first: (f64, u64, Option<f64>, String) = rmp_serde::decode::from_read(&mut reader).unwrap();
+        assert_eq!(first, (1.5, 100, None, "pair".to_string()));
+        let second: (f64, u64, Option<f64>, String) = rmp_serde::decode::from_read(&mut reader).unwrap();
+        assert_eq!(second.2, Some(1.2));
+        assert!(reader.is_empty());
+
+        for format in [Format::Csv, Format::JsonLines, Format::MessagePack] {
+            let table = read(format, write(format).as_slice()).unwrap();
+            let column = |name: &str| table.header.iter().position(|h| h == name).unwrap();
+            assert_eq!(table.rows.len(), 2);
+            for (row, expected) in table.rows.iter().zip([["1.5", "100", "", "pair"], ["1.6", "50", "1.2", "pair"]]) {
+                for (name, expected) in ["Lambda", "Runs", "Threshold", "Approximation"].into_iter().zip(expected) {
+                    assert_eq!(row[column(name)], expected, "{:?} {}", format, name);
+                }
+            }
+        }
+
+        //seeds above i64::MAX and flags keep their type
+        let mut out = Vec::new();
+        {
+            let mut wtr = writer(Format::JsonLines, &mut out);
+            wtr.write_header(&["Seed", "Died"]).unwrap();
+            wtr.write_record(&[u64::MAX.to_string(), true.to_string()]).unwrap();
+            wtr.flush().unwrap();
+        }
+        let row: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(row, serde_json::json!({"Seed": u64::MAX, "Died": true}));
+        let table = read(Format::JsonLines, out.as_slice()).unwrap();
+        let seed = table.header.iter().position(|h| h == "Seed").unwrap();
+        assert_eq!(table.rows[0][seed], u64::MAX.to_string());
+        assert_eq!(table.rows[0][1 - seed], "true");
+
+        //non-finite values such as the velocity without survivors survive the round trip
+        for format in [Format::JsonLines, Format::MessagePack] {
+            let mut out = Vec::new();
+            {
+                let mut wtr = writer(format, &mut out);
+                wtr.write_header(&["Velocity", "Residual", "Slope"]).unwrap();
+                wtr.write_record(&[f64::NAN.to_string(), f64::INFINITY.to_string(), f64::NEG_INFINITY.to_string()]).unwrap();
+                wtr.flush().unwrap();
+            }
+            let table = read(format, out.as_slice()).unwrap();
+            for (name, expected) in ["Velocity", "Residual", "Slope"].into_iter().zip(["NaN", "inf", "-inf"]) {
+                let column = table.header.iter().position(|h| h == name).unwrap();
+                assert_eq!(table.rows[0][column], expected, "{:?} {}", format, name);
+            }
+        }
+
+        assert_eq!(Format::from_path("results.jsonl"), Format::JsonLines);
+        assert_eq!(Format::from_path("replicas.msgpack"), Format::MessagePack);
+        assert_eq!(Format::from_path("results.csv"), Format::Csv);
+    }
+}
@@ -0,0 +1,190 @@
+use std::fs::File;
+use std::io::{self, BufWriter};
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use super::raster::{Colour, Raster, WHITE};
+use crate::analysis::sampling::sample;
+use crate::simulation::sim::Sim;
+use crate::simulation::sim2d;
+
+pub const INFECTED: Colour = [200, 30, 30];
+pub const HEALED: Colour = [150, 180, 220];
+pub const NEVER_INFECTED: Colour = WHITE;
+
+//hundredths of a second between two frames of an animation
+const GIF_DELAY: u16 = 10;
+
+/// The region of the plane drawn in every frame, `y` increases upwards.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Viewport {
+    /// The sites `x.0..=x.1` times `y.0..=y.1`, to compare runs at the same scale.
+    Fixed { x: (i32, i32), y: (i32, i32) },
+    /// The smallest square around the initially infected site which holds every site infected so far,
+    /// it zooms out as the region grows and keeps the aspect ratio of the spread.
+    #[default]
+    Auto,
+}
+
+impl Viewport {
+    /// Lower and upper bounds of the sites drawn along x and y.
+    pub fn bounds(&self, frame: &Frame) -> ((i32, i32), (i32, i32)) {
+        match *self {
+            Viewport::Fixed { x, y } => (x, y),
+            Viewport::Auto => {
+                let r = frame.ever_infected.iter().map(|&(x, y)| x.abs().max(y.abs())).max().unwrap_or(0).max(1);
+                ((-r, r), (-r, r))
+            }
+        }
+    }
+}
+
+/// State of a sim2d run at one sample time, the healed sites are the ever infected ones
+/// which are not infected any more.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Frame {
+    pub t: f64,
+    pub infected: Vec<(i32, i32)>,
+    pub ever_infected: Vec<(i32, i32)>,
+}
+
+impl Frame {
+    /// Runs `sim` until `t_max` and takes a frame at the times `0, dt, 2 dt, ...`.
+    pub fn record(sim: &mut sim2d::Simulation, t_max: f64, dt: f64) -> Vec<Frame> {
+        sample(sim, t_max, dt, |sim: &sim2d::Simulation| (sim.get_infected_sites(), sim.get_ever_infected_sites().collect()))
+            .into_iter()
+            .map(|(t, (infected, ever_infected))| Frame { t, infected, ever_infected })
+            .collect()
+    }
+
+    /// The longer side of the viewport gets `size` pixels. A site covers a block of pixels when
+    /// the viewport is smaller than the image, otherwise a pixel shows an infected site if it
+    /// covers one, else a healed site if it covers one.
+    pub fn raster(&self, viewport: Viewport, size: usize) -> Raster {
+        let ((x0, x1), (y0, y1)) = viewport.bounds(self);
+        let (columns, rows) = ((x1 - x0 + 1).max(1) as usize, (y1 - y0 + 1).max(1) as usize);
+        let longer = columns.max(rows);
+        let (width, height) = ((size * columns / longer).max(1), (size * rows / longer).max(1));
+        let mut raster = Raster::new(width, height, NEVER_INFECTED);
+        let mut draw = |sites: &[(i32, i32)], colour: Colour| {
+            for &(x, y) in sites {
+                if x < x0 || x > x1 || y < y0 || y > y1 {
+                    continue;
+                }
+                let (px, py) = (pixels((x - x0) as usize, columns, width), pixels((y1 - y) as usize, rows, height));
+                for py in py.0..py.1 {
+                    for px in px.0..px.1 {
+                        raster.set(px, py, colour);
+                    }
+                }
+            }
+        };
+        draw(&self.ever_infected, HEALED);
+        draw(&self.infected, INFECTED);
+        raster
+    }
+}
+
+//pixels covered by cell i of n cells drawn on p pixels, at least one
+fn pixels(i: usize, n: usize, p: usize) -> (usize, usize) {
+    let start = i * p / n;
+    (start, ((i + 1) * p / n).max(start + 1))
+}
+
+/// `frames.png` becomes `frames_007.png` for frame 7 of up to 1000.
+pub fn numbered_path<P: AsRef<Path>>(path: P, i: usize, n: usize) -> PathBuf {
+    let path = path.as_ref();
+    let digits = n.saturating_sub(1).to_string().len();
+    let stem = path.file_stem().and_then(|s| s.to_str()).unwrap_or("frame");
+    let mut name = format!("{}_{:0width$}", stem, i, width = digits);
+    if let Some(extension) = path.extension().and_then(|e| e.to_str()) {
+        name = format!("{}.{}", name, extension);
+    }
+    path.with_file_name(name)
+}
+
+/// Writes the frames as an animated GIF that loops forever. The frames of a fixed viewport and
+/// of the square auto-scaling one all have the same size.
+pub fn save_gif<P: AsRef<Path>>(frames: &[Frame], viewport: Viewport, size: usize, path: P) -> io::Result<()> {
+    let palette = [NEVER_INFECTED, HEALED, INFECTED];
+    let rasters: Vec<Raster> = frames.iter().map(|frame| frame.raster(viewport, size)).collect();
+    let (width, height) = rasters.first().map_or((1, 1), |r| (r.width, r.height));
+    let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+        (Ok(width), Ok(height)) => (width, height),
+        _ => return Err(io::Error::other(format!("a GIF can't be {} x {} pixels", width, height))),
+    };
+    let mut encoder = gif::Encoder::new(BufWriter::new(File::create(path)?), width, height, palette.as_flattened())
+        .map_err(io::Error::other)?;
+    encoder.set_repeat(gif::Repeat::Infinite).map_err(io::Error::other)?;
+    for raster in &rasters {
+        let mut indices = Vec::with_capacity(raster.width * raster.height);
+        for y in 0..raster.height {
+            for x in 0..raster.width {
+                let colour = raster.get(x, y);
+                indices.push(palette.iter().position(|&c| c == colour).unwrap_or(0) as u8);
+            }
+        }
+        let mut frame = gif::Frame::from_indexed_pixels(raster.width as u16, raster.height as u16, indices, None);
+        frame.delay = GIF_DELAY;
+        encoder.write_frame(&frame).map_err(io::Error::other)?;
+    }
+    Ok(())
+}
+
+/// `.gif` is written as one animation, everything else as numbered frames, see `numbered_path`
+/// and `Raster::save`.
+pub fn save<P: AsRef<Path>>(frames: &[Frame], viewport: Viewport, size: usize, path: P) -> io::Result<()> {
+    if path.as_ref().extension().and_then(|e| e.to_str()) == Some("gif") {
+        return save_gif(frames, viewport, size, path);
+    }
+    for (i, frame) in frames.iter().enumerate() {
+        frame.raster(viewport, size).save(numbered_path(&path, i, frames.len()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_frames() {
+        let frame = Frame { t: 1.0, infected: vec![(2, 0)], ever_infected: vec![(0, 0), (1, 0), (2, 0), (0, -1)] };
+        assert_eq!(Viewport::Auto.bounds(&frame), ((-2, 2), (-2, 2)));
+
+        //5 x 5 sites on 10 x 10 pixels, y = 0 is the middle row
+        let raster = frame.raster(Viewport::Auto, 10);
+        assert_eq!((raster.width, raster.height), (10, 10));
+        assert_eq!(raster.get(8, 4), INFECTED);
+        assert_eq!(raster.get(9, 5), INFECTED);
+        assert_eq!(raster.get(4, 4), HEALED);
+        assert_eq!(raster.get(4, 6), HEALED);
+        assert_eq!(raster.get(0, 0), NEVER_INFECTED);
+
+        //more sites than pixels, infected wins over healed
+        let raster = frame.raster(Viewport::Fixed { x: (0, 3), y: (-1, 0) }, 2);
+        assert_eq!((raster.width, raster.height), (2, 1));
+        assert_eq!(raster.get(1, 0), INFECTED);
+        assert_eq!(raster.get(0, 0), HEALED);
+
+        assert_eq!(numbered_path("out/frames.png", 7, 1000), PathBuf::from("out/frames_007.png"));
+        assert_eq!(numbered_path("frames.ppm", 0, 1), PathBuf::from("frames_0.ppm"));
+
+        let params = sim2d::ModelParams::new(2.0, 0.8).unwrap();
+        let frames = Frame::record(&mut sim2d::Simulation::with_seed(params, 5), 10.0, 1.0);
+        assert_eq!(frames.len(), 11);
+        assert_eq!(frames[0].infected, vec![(0, 0)]);
+        assert!(frames.windows(2).all(|w| w[0].ever_infected.len() <= w[1].ever_infected.len()));
+        let path = std::env::temp_dir().join(format!("frames_{}.gif", std::process::id()));
+        save(&frames, Viewport::Auto, 64, &path).unwrap();
+        let mut decoder = gif::DecodeOptions::new().read_info(File::open(&path).unwrap()).unwrap();
+        let mut n = 0;
+        while decoder.read_next_frame().unwrap().is_some() {
+            n += 1;
+        }
+        assert_eq!(n, frames.len());
+        std::fs::remove_file(path).unwrap();
+    }
+}
@@ -0,0 +1,3 @@
+pub mod frames;
+pub mod raster;
+pub mod spacetime;
@@ -0,0 +1,95 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+pub type Colour = [u8; 3];
+
+pub const WHITE: Colour = [255, 255, 255];
+pub const BLACK: Colour = [0, 0, 0];
+
+/// An RGB image, written without external tools as PNG or binary PPM.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raster {
+    pub width: usize,
+    pub height: usize,
+    /// Rows from top to bottom, three bytes per pixel.
+    pub pixels: Vec<u8>,
+}
+
+impl Raster {
+    pub fn new(width: usize, height: usize, background: Colour) -> Self {
+        Raster { width, height, pixels: background.repeat(width * height) }
+    }
+
+    /// Pixels outside the image are ignored.
+    pub fn set(&mut self, x: usize, y: usize, colour: Colour) {
+        if x < self.width && y < self.height {
+            let i = 3 * (y * self.width + x);
+            self.pixels[i..i + 3].copy_from_slice(&colour);
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Colour {
+        let i = 3 * (y * self.width + x);
+        [self.pixels[i], self.pixels[i + 1], self.pixels[i + 2]]
+    }
+
+    /// Fills the `scale` x `scale` block of the cell `(x, y)`.
+    pub fn fill_cell(&mut self, x: usize, y: usize, scale: usize, colour: Colour) {
+        for dy in 0..scale {
+            for dx in 0..scale {
+                self.set(x * scale + dx, y * scale + dy, colour);
+            }
+        }
+    }
+
+    pub fn write_png<W: Write>(&self, out: W) -> io::Result<()> {
+        let mut encoder = png::Encoder::new(out, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().map_err(io::Error::other)?;
+        writer.write_image_data(&self.pixels).map_err(io::Error::other)?;
+        writer.finish().map_err(io::Error::other)
+    }
+
+    pub fn write_ppm<W: Write>(&self, mut out: W) -> io::Result<()> {
+        write!(out, "P6\n{} {}\n255\n", self.width, self.height)?;
+        out.write_all(&self.pixels)?;
+        out.flush()
+    }
+
+    /// `.ppm` is written as PPM, everything else as PNG.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let out = BufWriter::new(File::create(&path)?);
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("ppm") => self.write_ppm(out),
+            _ => self.write_png(out),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_raster() {
+        let mut raster = Raster::new(3, 2, WHITE);
+        raster.set(2, 1, BLACK);
+        raster.set(3, 0, BLACK);
+        assert_eq!(raster.get(2, 1), BLACK);
+        assert_eq!(raster.get(0, 0), WHITE);
+
+        let mut ppm = Vec::new();
+        raster.write_ppm(&mut ppm).unwrap();
+        assert!(ppm.starts_with(b"P6\n3 2\n255\n"));
+        assert_eq!(ppm.len(), 11 + 18);
+
+        let mut png = Vec::new();
+        raster.write_png(&mut png).unwrap();
+        let mut reader = png::Decoder::new(io::Cursor::new(png)).read_info().unwrap();
+        let mut decoded = vec![0; reader.output_buffer_size().unwrap()];
+        reader.next_frame(&mut decoded).unwrap();
+        assert_eq!(decoded, raster.pixels);
+    }
+}
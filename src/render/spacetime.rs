@@ -0,0 +1,109 @@
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+use super::raster::{Raster, BLACK, WHITE};
+use crate::analysis::sampling::sample;
+use crate::simulation::sim::Sim;
+
+/// The infected sites of a 1D run at the times `0, dt, 2 dt, ...`, the classic space-time picture
+/// of the contact process with the sites on the x-axis and time increasing downwards.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SpaceTime {
+    pub dt: f64,
+    /// Sorted infected sites of every sample.
+    pub rows: Vec<Vec<i32>>,
+}
+
+impl SpaceTime {
+    /// Runs `sim` until `t_max`, the y coordinate of the sites is ignored.
+    pub fn record(sim: &mut dyn Sim, t_max: f64, dt: f64) -> Self {
+        let rows = sample(sim, t_max, dt, |sim| {
+            let mut sites: Vec<i32> = sim.get_infected_sites().into_iter().map(|(x, _)| x).collect();
+            sites.sort_unstable();
+            sites
+        });
+        SpaceTime { dt, rows: rows.into_iter().map(|(_, sites)| sites).collect() }
+    }
+
+    /// Leftmost and rightmost site infected at any sample, (0, 0) if there is none.
+    pub fn extent(&self) -> (i32, i32) {
+        let min = self.rows.iter().filter_map(|r| r.first()).min().copied();
+        let max = self.rows.iter().filter_map(|r| r.last()).max().copied();
+        min.zip(max).unwrap_or((0, 0))
+    }
+
+    /// One `scale` x `scale` block per site and sample, infected sites are black.
+    pub fn raster(&self, scale: usize) -> Raster {
+        let (min, max) = self.extent();
+        let width = (max - min + 1) as usize;
+        let mut raster = Raster::new(width * scale, self.rows.len() * scale, WHITE);
+        for (y, row) in self.rows.iter().enumerate() {
+            for &site in row {
+                raster.fill_cell((site - min) as usize, y, scale, BLACK);
+            }
+        }
+        raster
+    }
+
+    //runs of neighbouring infected sites become one rectangle
+    pub fn write_svg<W: Write>(&self, mut out: W, scale: usize) -> io::Result<()> {
+        let (min, max) = self.extent();
+        let (width, height) = ((max - min + 1) as usize * scale, self.rows.len() * scale);
+        writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" shape-rendering="crispEdges">"#,
+                 w = width, h = height)?;
+        writeln!(out, r#"<rect width="{}" height="{}" fill="white"/>"#, width, height)?;
+        for (y, row) in self.rows.iter().enumerate() {
+            let mut i = 0;
+            while i < row.len() {
+                let start = row[i];
+                while i + 1 < row.len() && row[i + 1] == row[i] + 1 {
+                    i += 1;
+                }
+                writeln!(out, r#"<rect x="{}" y="{}" width="{}" height="{}"/>"#,
+                         (start - min) as usize * scale, y * scale, (row[i] - start + 1) as usize * scale, scale)?;
+                i += 1;
+            }
+        }
+        writeln!(out, "</svg>")?;
+        out.flush()
+    }
+
+    /// `.svg` is written as SVG, everything else as a raster image, see `Raster::save`.
+    pub fn save<P: AsRef<Path>>(&self, path: P, scale: usize) -> io::Result<()> {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some("svg") => self.write_svg(BufWriter::new(File::create(path)?), scale),
+            _ => self.raster(scale).save(path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::registry;
+
+    #[test]
+    fn test_spacetime() {
+        let spacetime = SpaceTime { dt: 1.0, rows: vec![vec![0], vec![-1, 0, 2], vec![]] };
+        assert_eq!(spacetime.extent(), (-1, 2));
+        let raster = spacetime.raster(2);
+        assert_eq!((raster.width, raster.height), (8, 6));
+        assert_eq!(raster.get(2, 0), BLACK);
+        assert_eq!(raster.get(0, 0), WHITE);
+        assert_eq!(raster.get(5, 3), WHITE);
+        assert_eq!(raster.get(7, 3), BLACK);
+
+        let mut svg = Vec::new();
+        spacetime.write_svg(&mut svg, 2).unwrap();
+        let svg = String::from_utf8(svg).unwrap();
+        assert!(svg.contains(r#"<rect x="0" y="2" width="4" height="2"/>"#));
+        assert_eq!(svg.matches("<rect").count(), 4);
+
+        let mut sim = registry::find("sim1d_rb").unwrap().create_with_seed(&[2.0], 3).unwrap();
+        let spacetime = SpaceTime::record(sim.as_mut(), 20.0, 0.5);
+        assert_eq!(spacetime.rows.len(), 41);
+        assert_eq!(spacetime.rows[0].len(), 1);
+        assert!(spacetime.rows.iter().all(|r| r.windows(2).all(|w| w[0] < w[1])));
+    }
+}
@@ -0,0 +1,142 @@
+use std::io;
+use std::sync::mpsc::{self, Sender};
+use std::thread::{self, JoinHandle};
+
+use crate::manifest::Manifest;
+use crate::output;
+use crate::simulation::sim::Sim;
+use crate::store::{self, Store};
+
+/// Raw outcome of a single run, for analyses the aggregated results don't cover.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Replica {
+    pub lambda: f64,
+    pub alpha: Option<f64>,
+    /// Index of the replica at its grid point, see `sweep::replica_seed`.
+    pub replica: usize,
+    pub seed: u64,
+    pub died: bool,
+    /// Time of the last event if the disease died before t_max.
+    pub extinction_time: Option<f64>,
+    pub final_infected: usize,
+    pub max_infected: usize,
+    pub events: u64,
+}
+
+impl Replica {
+    /// `point` holds lambda and optionally alpha, `died` and `events` are the result of `Sim::run_counting`.
+    pub fn new(point: &[f64], replica: usize, seed: u64, sim: &dyn Sim, died: bool, events: u64) -> Self {
+        Replica {
+            lambda: point[0],
+            alpha: point.get(1).copied(),
+            replica,
+            seed,
+            died,
+            extinction_time: if died { Some(sim.get_t()) } else { None },
+            final_infected: sim.get_number_of_infected_nodes(),
+            max_infected: sim.get_outbreak_stats().peak,
+            events,
+        }
+    }
+
+    pub fn header() -> [&'static str; 9] {
+        ["Lambda", "Alpha", "Replica", "Seed", "Died", "Extinction Time", "Final Infected", "Max Infected", "Events"]
+    }
+
+    pub fn record(&self) -> [String; 9] {
+        let optional = |v: Option<f64>| v.map(|v| v.to_string()).unwrap_or_default();
+        [
+            self.lambda.to_string(),
+            optional(self.alpha),
+            self.replica.to_string(),
+            self.seed.to_string(),
+            self.died.to_string(),
+            optional(self.extinction_time),
+            self.final_infected.to_string(),
+            self.max_infected.to_string(),
+            self.events.to_string(),
+        ]
+    }
+}
+
+/// Writes replicas on its own thread as the workers finish them, in the format of the output path
+/// or into the `replicas` table of a store. Replicas restored from a checkpoint are not written again.
+pub struct ReplicaStream {
+    sender: Sender<Replica>,
+    writer: JoinHandle<io::Result<()>>,
+}
+
+impl ReplicaStream {
+    pub fn spawn(output: &str, manifest: Manifest) -> Self {
+        let (sender, receiver) = mpsc::channel::<Replica>();
+        let output = output.to_string();
+        let writer = thread::spawn(move || {
+            if store::is_database(&output) {
+                let mut store = Store::open(&output).map_err(io::Error::other)?;
+                let mut replicas = store.add_replicas(&manifest).map_err(io::Error::other)?;
+                for replica in receiver {
+                    replicas.insert(&replica).map_err(io::Error::other)?;
+                }
+                replicas.commit(&manifest.finished()).map_err(io::Error::other)
+            } else {
+                let mut wtr = output::create(&output)?;
+                wtr.write_header(&Replica::header())?;
+                for replica in receiver {
+                    wtr.write_record(&replica.record())?;
+                }
+                wtr.flush()?;
+                manifest.write_next_to(&output)
+            }
+        });
+        ReplicaStream { sender, writer }
+    }
+
+    /// Every worker gets its own sender.
+    pub fn sender(&self) -> Sender<Replica> {
+        self.sender.clone()
+    }
+
+    /// Waits until every replica sent so far is written, the workers have to be done.
+    pub fn close(self) -> io::Result<()> {
+        drop(self.sender);
+        self.writer.join().unwrap_or_else(|_| Err(io::Error::other("replica writer panicked")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::registry;
+
+    #[test]
+    fn test_stream() {
+        let path = std::env::temp_dir().join(format!("replicas_{}.csv", std::process::id()));
+        let path = path.to_str().unwrap();
+        let stream = ReplicaStream::spawn(path, Manifest::new("test"));
+        let model = registry::find("sim1d").unwrap();
+        let mut expected = Vec::new();
+        for replica in 0..5 {
+            let mut sim = model.create_with_seed(&[1.2], replica).unwrap();
+            let (died, events) = sim.run_counting(50.0);
+            //every event infects or heals a node
+            assert!(events as usize >= sim.get_outbreak_stats().infections);
+            expected.push(Replica::new(&[1.2], replica as usize, replica, sim.as_ref(), died, events));
+        }
+        let sender = stream.sender();
+        for replica in &expected {
+            sender.send(replica.clone()).unwrap();
+        }
+        drop(sender);
+        stream.close().unwrap();
+
+        let mut rdr = csv::Reader::from_path(path).unwrap();
+        assert_eq!(rdr.headers().unwrap(), &Replica::header()[..]);
+        let rows: Vec<csv::StringRecord> = rdr.records().map(|r| r.unwrap()).collect();
+        assert_eq!(rows.len(), expected.len());
+        for (row, replica) in rows.iter().zip(&expected) {
+            assert_eq!(row, &replica.record()[..]);
+        }
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(Manifest::path_for(path)).unwrap();
+    }
+}
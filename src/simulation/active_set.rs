@@ -0,0 +1,143 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use super::sim::{BoundingBox, OutbreakStats};
+
+/// Keeps track of the currently infected sites so that the number of infected
+/// nodes and their extent can be read without iterating over every stored node.
+/// Each axis keeps a count per coordinate, 1D models simply use `y = 0`.
+/// Since every infection passes through here it also keeps the outbreak statistics.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActiveSet {
+    count: usize,
+    xs: BTreeMap<i32, usize>,
+    ys: BTreeMap<i32, usize>,
+    squared_distance_sum: i64,
+    infections: usize,
+    peak: usize,
+    peak_t: f64,
+}
+
+impl ActiveSet {
+    pub fn new() -> Self {
+        ActiveSet {
+            count: 0,
+            xs: BTreeMap::new(),
+            ys: BTreeMap::new(),
+            squared_distance_sum: 0,
+            infections: 0,
+            peak: 0,
+            peak_t: 0.0,
+        }
+    }
+
+    pub fn insert(&mut self, x: i32, y: i32, t: f64) {
+        self.count += 1;
+        self.infections += 1;
+        if self.count > self.peak {
+            self.peak = self.count;
+            self.peak_t = t;
+        }
+        *self.xs.entry(x).or_insert(0) += 1;
+        *self.ys.entry(y).or_insert(0) += 1;
+        self.squared_distance_sum += Self::squared_distance(x, y);
+    }
+
+    pub fn remove(&mut self, x: i32, y: i32) {
+        self.count -= 1;
+        Self::decrement(&mut self.xs, x);
+        Self::decrement(&mut self.ys, y);
+        self.squared_distance_sum -= Self::squared_distance(x, y);
+    }
+
+    fn squared_distance(x: i32, y: i32) -> i64 {
+        x as i64 * x as i64 + y as i64 * y as i64
+    }
+
+    fn decrement(axis: &mut BTreeMap<i32, usize>, key: i32) {
+        let count = axis.get_mut(&key).expect("site is not infected");
+        *count -= 1;
+        if *count == 0 {
+            axis.remove(&key);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    /// Sum of the squared distances of all infected sites from the origin.
+    pub fn squared_distance_sum(&self) -> f64 {
+        self.squared_distance_sum as f64
+    }
+
+    pub fn bounding_box(&self) -> Option<BoundingBox> {
+        let (&min_x, _) = self.xs.first_key_value()?;
+        let (&max_x, _) = self.xs.last_key_value()?;
+        let (&min_y, _) = self.ys.first_key_value()?;
+        let (&max_y, _) = self.ys.last_key_value()?;
+        Some(BoundingBox { min_x, max_x, min_y, max_y })
+    }
+
+    /// `ever_infected` has to be supplied by the model, it is the number of distinct sites it stores.
+    pub fn outbreak_stats(&self, ever_infected: usize) -> OutbreakStats {
+        OutbreakStats {
+            ever_infected,
+            infections: self.infections,
+            peak: self.peak,
+            peak_t: self.peak_t,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::registry::test_models;
+
+    #[test]
+    fn test_active_set() {
+        let mut active = ActiveSet::new();
+        active.insert(0, 0, 0.0);
+        active.insert(-2, 1, 1.0);
+        active.insert(3, 1, 2.0);
+        active.remove(-2, 1);
+        assert_eq!(active.len(), 2);
+        assert_eq!(active.bounding_box(), Some(BoundingBox { min_x: 0, max_x: 3, min_y: 0, max_y: 1 }));
+        assert_eq!(active.squared_distance_sum(), 10.0);
+        let stats = active.outbreak_stats(3);
+        assert_eq!((stats.infections, stats.peak, stats.peak_t), (3, 3, 2.0));
+        active.remove(0, 0);
+        active.remove(3, 1);
+        assert_eq!(active.bounding_box(), None);
+    }
+
+    //the incremental counts of every engine agree with a full recount of its infected sites
+    #[test]
+    fn test_matches_recount() {
+        for (model, values) in test_models(5.0, 0.7) {
+            let mut infections = 0;
+            for seed in 0..10 {
+                let mut sim = model.create_with_seed(&values, seed).unwrap();
+                for _ in 0..2000 {
+                    let sites = sim.get_infected_sites();
+                    assert_eq!(sim.get_number_of_infected_nodes(), sites.len(), "{}", model.name);
+                    let recount = sites.iter().fold(None, |b: Option<BoundingBox>, &(x, y)| Some(match b {
+                        None => BoundingBox { min_x: x, max_x: x, min_y: y, max_y: y },
+                        Some(b) => BoundingBox { min_x: b.min_x.min(x), max_x: b.max_x.max(x), min_y: b.min_y.min(y), max_y: b.max_y.max(y) },
+                    }));
+                    assert_eq!(sim.get_bounding_box(), recount, "{}", model.name);
+                    let squared: i64 = sites.iter().map(|&(x, y)| x as i64 * x as i64 + y as i64 * y as i64).sum();
+                    assert_eq!(sim.get_squared_distance_sum(), squared as f64, "{}", model.name);
+                    if !sim.step() {
+                        break;
+                    }
+                }
+                infections += sim.get_outbreak_stats().infections;
+            }
+            //runs that die right away would test nothing
+            assert!(infections > 1000, "{}", model.name);
+        }
+    }
+}
@@ -0,0 +1,230 @@
+use rand::{Rng, SeedableRng};
+use rand::distributions::Distribution;
+use rand_distr::Exp1;
+use serde::{Deserialize, Serialize};
+
+use super::sim::*;
+use super::snapshot::Snapshot;
+
+const NOT_INFECTED: usize = usize::MAX;
+
+/// Contact process on a periodic ring (`dimensions == 1`) or an L x L torus (`dimensions == 2`).
+/// Rates follow the infinite models: in 1D every infected site infects each neighbour with
+/// rate lambda, in 2D with rate lambda * alpha horizontally and lambda * (1 - alpha) vertically.
+/// Since the lattice is finite the infected sites are kept in a list and the next event
+/// is drawn with the Gillespie algorithm instead of keeping clocks per node.
+/// The unit rate waiting time is drawn ahead so the time of the next event is known before `step`.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Simulation {
+    l: usize,
+    dimensions: usize,
+    neighbours: Vec<((i32, i32), f64)>,
+    total_rate: f64,
+    position: Vec<usize>,
+    infected: Vec<usize>,
+    ever_infected: Vec<bool>,
+    ever_infected_count: usize,
+    infections: usize,
+    peak: usize,
+    peak_t: f64,
+    t: f64,
+    waiting: f64,
+    rng: SimRng,
+}
+
+impl Sim for Simulation {
+
+    fn step(&mut self) -> bool {
+        if self.infected.is_empty() {
+            return false;
+        }
+        self.t = self.get_next_t();
+        self.waiting = Exp1.sample(&mut self.rng);
+
+        let site = self.infected[self.rng.gen_range(0..self.infected.len())];
+        let mut u = self.rng.gen::<f64>() * self.total_rate;
+        if u < 1.0 {
+            self.heal(site);
+            return true;
+        }
+        u -= 1.0;
+        for &(offset, rate) in &self.neighbours {
+            if u < rate {
+                let neighbour = self.neighbour(site, offset);
+                self.infect(neighbour);
+                return true;
+            }
+            u -= rate;
+        }
+        true
+    }
+
+    fn get_number_of_infected_nodes(&self) -> usize {
+        self.infected.len()
+    }
+
+    fn get_t(&self) -> f64 {
+        self.t
+    }
+
+    fn get_next_t(&self) -> f64 {
+        self.t + self.waiting / (self.infected.len() as f64 * self.total_rate)
+    }
+
+    /// Coordinates are taken in [0, L), this scans all infected sites.
+    fn get_bounding_box(&self) -> Option<BoundingBox> {
+        let mut sites = self.infected.iter().map(|&site| self.coordinates(site));
+        let (x, y) = sites.next()?;
+        let mut bounding_box = BoundingBox { min_x: x, max_x: x, min_y: y, max_y: y };
+        for (x, y) in sites {
+            bounding_box.min_x = bounding_box.min_x.min(x);
+            bounding_box.max_x = bounding_box.max_x.max(x);
+            bounding_box.min_y = bounding_box.min_y.min(y);
+            bounding_box.max_y = bounding_box.max_y.max(y);
+        }
+        Some(bounding_box)
+    }
+
+    fn get_outbreak_stats(&self) -> OutbreakStats {
+        OutbreakStats {
+            ever_infected: self.ever_infected_count,
+            infections: self.infections,
+            peak: self.peak,
+            peak_t: self.peak_t,
+        }
+    }
+
+    /// Uses the shortest distance around the periodic boundary, this scans all infected sites.
+    fn get_squared_distance_sum(&self) -> f64 {
+        let l = self.l as i32;
+        self.infected.iter().map(|&site| {
+            let (x, y) = self.coordinates(site);
+            let (x, y) = (x.min(l - x) as f64, y.min(l - y) as f64);
+            x * x + y * y
+        }).sum()
+    }
+
+    fn get_infected_sites(&self) -> Vec<(i32, i32)> {
+        self.infected.iter().map(|&site| self.coordinates(site)).collect()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot::Finite(self.clone())
+    }
+}
+
+/// Offsets of the neighbours of a site together with the infection rate towards them.
+fn neighbour_offsets(dimensions: usize, lambda: f64, alpha: f64) -> Vec<((i32, i32), f64)> {
+    if dimensions == 1 {
+        vec![((-1, 0), lambda), ((1, 0), lambda)]
+    } else {
+        vec![
+            ((-1, 0), lambda * alpha),
+            ((1, 0), lambda * alpha),
+            ((0, -1), lambda * (1.0 - alpha)),
+            ((0, 1), lambda * (1.0 - alpha)),
+        ]
+    }
+}
+
+/// For every site the sites it infects and the rates, with the same topology as `Simulation`.
+pub fn neighbour_rates(l: usize, dimensions: usize, lambda: f64, alpha: f64) -> Vec<Vec<(usize, f64)>> {
+    let sim = Simulation::empty(l, dimensions, lambda, alpha, 0);
+    (0..sim.size())
+        .map(|site| sim.neighbours.iter().map(|&(offset, rate)| (sim.neighbour(site, offset), rate)).collect())
+        .collect()
+}
+
+impl Simulation {
+
+    /// Starts with every site infected, the random numbers are drawn from a generator seeded with `seed`.
+    pub fn with_seed(l: usize, dimensions: usize, lambda: f64, alpha: f64, seed: u64) -> Self {
+        let mut sim = Self::empty(l, dimensions, lambda, alpha, seed);
+        for site in 0..sim.size() {
+            sim.infect(site);
+        }
+        sim
+    }
+
+    /// Starts with a single infected site, seeded like `with_seed`.
+    #[cfg(test)]
+    pub fn new_single_seed(l: usize, dimensions: usize, lambda: f64, alpha: f64, seed: u64) -> Self {
+        let mut sim = Self::empty(l, dimensions, lambda, alpha, seed);
+        sim.infect(0);
+        sim
+    }
+
+    fn empty(l: usize, dimensions: usize, lambda: f64, alpha: f64, seed: u64) -> Self {
+        let neighbours = neighbour_offsets(dimensions, lambda, alpha);
+        let total_rate = 1.0 + neighbours.iter().map(|(_, rate)| rate).sum::<f64>();
+        let size = l.pow(dimensions as u32);
+        let mut rng = SimRng::seed_from_u64(seed);
+        Simulation {
+            l,
+            dimensions,
+            neighbours,
+            total_rate,
+            position: vec![NOT_INFECTED; size],
+            infected: Vec::with_capacity(size),
+            ever_infected: vec![false; size],
+            ever_infected_count: 0,
+            infections: 0,
+            peak: 0,
+            peak_t: 0.0,
+            t: 0.0,
+            waiting: Exp1.sample(&mut rng),
+            rng,
+        }
+    }
+
+    pub fn size(&self) -> usize {
+        self.position.len()
+    }
+
+    pub fn get_density(&self) -> f64 {
+        self.infected.len() as f64 / self.size() as f64
+    }
+
+    fn coordinates(&self, site: usize) -> (i32, i32) {
+        ((site % self.l) as i32, (site / self.l) as i32)
+    }
+
+    fn neighbour(&self, site: usize, (dx, dy): (i32, i32)) -> usize {
+        let l = self.l as i32;
+        let (x, y) = self.coordinates(site);
+        let x = (x + dx).rem_euclid(l);
+        if self.dimensions == 1 {
+            x as usize
+        } else {
+            let y = (y + dy).rem_euclid(l);
+            (y * l + x) as usize
+        }
+    }
+
+    fn infect(&mut self, site: usize) {
+        if self.position[site] != NOT_INFECTED {
+            return;
+        }
+        self.position[site] = self.infected.len();
+        self.infected.push(site);
+        self.infections += 1;
+        if !self.ever_infected[site] {
+            self.ever_infected[site] = true;
+            self.ever_infected_count += 1;
+        }
+        if self.infected.len() > self.peak {
+            self.peak = self.infected.len();
+            self.peak_t = self.t;
+        }
+    }
+
+    fn heal(&mut self, site: usize) {
+        let index = self.position[site];
+        let last = *self.infected.last().unwrap();
+        self.infected.swap_remove(index);
+        if last != site {
+            self.position[last] = index;
+        }
+        self.position[site] = NOT_INFECTED;
+    }
+}
@@ -5,4 +5,10 @@ pub(crate) mod sim;
 pub mod sim1d_b;
 pub mod sim1d_rb;
 pub(crate) mod rbtree;
-
+pub(crate) mod active_set;
+pub mod finite;
+pub mod registry;
+pub mod params;
+pub mod snapshot;
+#[cfg(test)]
+mod validation;
@@ -0,0 +1,125 @@
+use std::fmt::Display;
+
+/// A parameter with its range of valid values. Both bounds are inclusive but values have to be finite.
+pub struct Parameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub min: f64,
+    pub max: f64,
+}
+
+pub const LAMBDA: Parameter = Parameter {
+    name: "lambda",
+    description: "total infection rate",
+    min: 0.0,
+    max: f64::INFINITY,
+};
+
+pub const ALPHA: Parameter = Parameter {
+    name: "alpha",
+    description: "fraction of the infection rate along the first axis, nearest neighbours in 1D and horizontal in 2D",
+    min: 0.0,
+    max: 1.0,
+};
+
+pub const T_MAX: Parameter = Parameter {
+    name: "t_max",
+    description: "time at which a run that is still alive is stopped",
+    min: 0.0,
+    max: f64::INFINITY,
+};
+
+pub const DT: Parameter = Parameter {
+    name: "dt",
+    description: "time between two samples of a recorded run",
+    min: f64::MIN_POSITIVE,
+    max: f64::INFINITY,
+};
+
+pub const SIMULATIONS: Parameter = Parameter {
+    name: "simulations",
+    description: "runs per grid point",
+    min: 1.0,
+    max: f64::INFINITY,
+};
+
+pub const DIMENSIONS: Parameter = Parameter {
+    name: "dimensions",
+    description: "1 for a ring, 2 for a torus",
+    min: 1.0,
+    max: 2.0,
+};
+
+pub const L: Parameter = Parameter {
+    name: "l",
+    description: "side length of a finite lattice",
+    min: 1.0,
+    max: f64::INFINITY,
+};
+
+pub const N_ANGLES: Parameter = Parameter {
+    name: "n_angles",
+    description: "number of sectors of a shape profile",
+    min: 1.0,
+    max: f64::INFINITY,
+};
+
+pub const BATCH_SIZE: Parameter = Parameter {
+    name: "batch_size",
+    description: "runs per batch of the adaptive sweep",
+    min: 1.0,
+    max: f64::INFINITY,
+};
+
+pub const TARGET_WIDTH: Parameter = Parameter {
+    name: "target_width",
+    description: "width of the confidence interval at which the adaptive sweep stops",
+    min: f64::MIN_POSITIVE,
+    max: 1.0,
+};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParamError {
+    OutOfRange { name: &'static str, value: f64, min: f64, max: f64 },
+    WrongCount { model: &'static str, expected: usize, got: usize },
+}
+
+impl Display for ParamError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParamError::OutOfRange { name, value, min, max } =>
+                write!(f, "{} = {} is not in [{}, {}]", name, value, min, max),
+            ParamError::WrongCount { model, expected, got } =>
+                write!(f, "{} takes {} parameters but got {}", model, expected, got),
+        }
+    }
+}
+
+impl std::error::Error for ParamError {}
+
+impl Parameter {
+    /// Returns the value if it is in range.
+    pub fn check(&self, value: f64) -> Result<f64, ParamError> {
+        if value.is_finite() && value >= self.min && value <= self.max {
+            Ok(value)
+        } else {
+            Err(ParamError::OutOfRange { name: self.name, value, min: self.min, max: self.max })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(LAMBDA.check(1.5), Ok(1.5));
+        assert_eq!(ALPHA.check(1.0), Ok(1.0));
+        assert!(LAMBDA.check(-0.1).is_err());
+        assert!(LAMBDA.check(f64::NAN).is_err());
+        assert!(ALPHA.check(1.1).is_err());
+        assert!(T_MAX.check(f64::INFINITY).is_err());
+        assert_eq!(ALPHA.check(-1.0).unwrap_err().to_string(), "alpha = -1 is not in [0, 1]");
+    }
+}
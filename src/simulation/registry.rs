@@ -0,0 +1,124 @@
+use std::fmt::Display;
+
+use super::params::*;
+use super::sim::Sim;
+use super::{sim1d, sim1d_b, sim1d_rb, sim2d};
+
+/// Creates a simulation from the parameter values and a seed.
+type Constructor = fn(&[f64], u64) -> Result<Box<dyn Sim>, ParamError>;
+
+/// A model addressable by name. `create` gets the values in the order of `parameters`
+/// and validates them through the `ModelParams` of the model.
+pub struct Model {
+    pub name: &'static str,
+    pub description: &'static str,
+    /// 1 for the models on a line, 2 for the plane.
+    pub dimensions: usize,
+    pub parameters: &'static [Parameter],
+    create: Constructor,
+}
+
+impl Model {
+    pub fn create_with_seed(&self, values: &[f64], seed: u64) -> Result<Box<dyn Sim>, ParamError> {
+        self.validate(values)?;
+        (self.create)(values, seed)
+    }
+
+    /// Checks the values against the parameter schema without creating a simulation.
+    pub fn validate(&self, values: &[f64]) -> Result<(), ParamError> {
+        if values.len() != self.parameters.len() {
+            return Err(ParamError::WrongCount { model: self.name, expected: self.parameters.len(), got: values.len() });
+        }
+        for (parameter, &value) in self.parameters.iter().zip(values) {
+            parameter.check(value)?;
+        }
+        Ok(())
+    }
+
+    pub fn parameter_names(&self) -> Vec<&'static str> {
+        self.parameters.iter().map(|p| p.name).collect()
+    }
+}
+
+impl Display for Model {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parameters: Vec<String> = self.parameters.iter()
+            .map(|p| format!("{} in [{}, {}]", p.name, p.min, p.max))
+            .collect();
+        write!(f, "{}({}): {}", self.name, parameters.join(", "), self.description)?;
+        for parameter in self.parameters {
+            write!(f, "\n    {}: {}", parameter.name, parameter.description)?;
+        }
+        Ok(())
+    }
+}
+
+pub const MODELS: &[Model] = &[
+    Model {
+        name: "sim1d",
+        description: "1D contact process, infects both neighbours with rate lambda",
+        dimensions: 1,
+        parameters: &[LAMBDA],
+        create: |v, seed| Ok(Box::new(sim1d::Simulation::with_seed(sim1d::ModelParams::new(v[0])?, seed))),
+    },
+    Model {
+        name: "sim1d_rb",
+        description: "same process as sim1d, the next event is kept in a red black tree",
+        dimensions: 1,
+        parameters: &[LAMBDA],
+        create: |v, seed| Ok(Box::new(sim1d_rb::Simulation::with_seed(sim1d_rb::ModelParams::new(v[0])?, seed))),
+    },
+    Model {
+        name: "sim1d_b",
+        description: "1D with rate lambda * alpha to the nearest and lambda * (1 - alpha) to the next nearest neighbours",
+        dimensions: 1,
+        parameters: &[LAMBDA, ALPHA],
+        create: |v, seed| Ok(Box::new(sim1d_b::Simulation::with_seed(sim1d_b::ModelParams::new(v[0], v[1])?, seed))),
+    },
+    Model {
+        name: "sim2d",
+        description: "2D with rate lambda * alpha horizontally and lambda * (1 - alpha) vertically",
+        dimensions: 2,
+        parameters: &[LAMBDA, ALPHA],
+        create: |v, seed| Ok(Box::new(sim2d::Simulation::with_seed(sim2d::ModelParams::new(v[0], v[1])?, seed))),
+    },
+];
+
+/// Looks up a model by name, the error lists the known models.
+pub fn find(name: &str) -> Result<&'static Model, String> {
+    MODELS.iter().find(|m| m.name == name).ok_or_else(|| {
+        let known: Vec<&str> = MODELS.iter().map(|m| m.name).collect();
+        format!("unknown model {}, known models are {}", name, known.join(", "))
+    })
+}
+
+/// Every model with its parameter values, `lambda` for the infection rate and `alpha` for the others.
+/// For the tests which run all models.
+#[cfg(test)]
+pub fn test_models(lambda: f64, alpha: f64) -> impl Iterator<Item = (&'static Model, Vec<f64>)> {
+    MODELS.iter().map(move |model| {
+        (model, model.parameters.iter().map(|p| if p.name == LAMBDA.name { lambda } else { alpha }).collect())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        assert!(find("sim3d").is_err());
+        assert!(find("sim2d").unwrap().create_with_seed(&[1.0], 0).is_err());
+        assert!(find("sim2d").unwrap().create_with_seed(&[1.0, 1.5], 0).is_err());
+        assert!(find("sim1d").unwrap().create_with_seed(&[f64::NAN], 0).is_err());
+        for (model, values) in test_models(0.5, 0.5) {
+            assert_eq!(find(model.name).unwrap().name, model.name);
+            assert!(model.parameters.iter().all(|p| model.to_string().contains(p.description)));
+            let mut a = model.create_with_seed(&values, 7).unwrap();
+            let mut b = model.create_with_seed(&values, 7).unwrap();
+            a.run(5.0);
+            b.run(5.0);
+            assert_eq!(a.get_t(), b.get_t());
+        }
+    }
+}
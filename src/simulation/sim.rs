@@ -1,23 +1,62 @@
-use super::sim2d;
-use super::sim1d;
-use super::sim1d_b;
+use serde::{Deserialize, Serialize};
 
-pub trait Sim {
-    fn run(&mut self, t_max: f64) -> bool;
-    fn get_number_of_infected_nodes(&self) -> usize;
+use super::snapshot::Snapshot;
+
+/// Random number generator of every engine, seedable so that runs can be reproduced.
+pub type SimRng = rand_pcg::Pcg64;
 
+/// Smallest axis aligned rectangle containing every infected site.
+/// 1D models always report `min_y == max_y == 0`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BoundingBox {
+    pub min_x: i32,
+    pub max_x: i32,
+    pub min_y: i32,
+    pub max_y: i32,
 }
 
-#[derive(Clone, Copy)]
-pub enum SimAlphaType {
-    TwoD,
-    OneDB,
+/// Statistics of a single run. `infections` counts every transition from healthy
+/// to infected including the initial node, `peak` is the maximum number of
+/// simultaneously infected nodes which was first reached at `peak_t`.
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct OutbreakStats {
+    pub ever_infected: usize,
+    pub infections: usize,
+    pub peak: usize,
+    pub peak_t: f64,
 }
 
-#[inline]
-pub fn create_sim(sim_type: SimAlphaType, lambda: f64, alpha: f64) -> Box<dyn Sim> {
-    match sim_type {
-        SimAlphaType::TwoD => Box::new(sim2d::Simulation::new(lambda, alpha)),
-        SimAlphaType::OneDB => Box::new(sim1d_b::Simulation::new(lambda, alpha)),
+pub trait Sim {
+    /// Executes the next event, returns false if no infected node is left.
+    fn step(&mut self) -> bool;
+    fn get_number_of_infected_nodes(&self) -> usize;
+    fn get_t(&self) -> f64;
+    /// Time of the next event without executing it, infinite if no infected node is left.
+    fn get_next_t(&self) -> f64;
+    fn get_bounding_box(&self) -> Option<BoundingBox>;
+    fn get_outbreak_stats(&self) -> OutbreakStats;
+    /// Sum of the squared distances of the infected sites from the initial node.
+    fn get_squared_distance_sum(&self) -> f64;
+    /// Every currently infected site, 1D models report `y = 0`.
+    fn get_infected_sites(&self) -> Vec<(i32, i32)>;
+    /// Full state of the engine, see `Snapshot`.
+    fn snapshot(&self) -> Snapshot;
+
+    /// Returns true if the disease died before t_max.
+    fn run(&mut self, t_max: f64) -> bool {
+        self.run_counting(t_max).0
+    }
+
+    /// Like `run`, also returns the number of executed events.
+    fn run_counting(&mut self, t_max: f64) -> (bool, u64) {
+        let mut events = 0;
+        while self.get_t() < t_max {
+            let result = self.step();
+            if !result {
+                return (true, events)
+            }
+            events += 1;
+        }
+        (false, events)
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use rand::SeedableRng;
 use rand_distr::Exp;
+use serde::{Deserialize, Serialize};
 
 
 
@@ -12,34 +14,44 @@ use node::Node;
 use utils::State;
 use utils::InfectProgress;
 
+use super::sim::*;
+use super::active_set::ActiveSet;
+use super::params::*;
+use super::snapshot::Snapshot;
+
+
+/// Validated parameters of the model.
+#[derive(Clone, Copy, Debug)]
+pub struct ModelParams {
+    lambda: f64,
+}
+
+impl ModelParams {
+    pub fn new(lambda: f64) -> Result<Self, ParamError> {
+        Ok(ModelParams { lambda: LAMBDA.check(lambda)? })
+    }
+
+    pub fn lambda(&self) -> f64 {
+        self.lambda
+    }
+}
 
 pub struct Simulation {
+    params: ModelParams,
     nodes: HashMap<i32, Node>,
     t: f64,
-    exp: Exp<f64>
+    /// Node and time of the next event, found once per event by `next_event`.
+    next: Option<(i32, f64)>,
+    exp: Exp<f64>,
+    active: ActiveSet,
+    rng: SimRng,
 }
 
-impl Simulation {
-    pub fn new(lambda: f64) -> Self {
-        let mut nodes = HashMap::new();
-        let exp = Exp::new(lambda).unwrap();
-        let start_node = Node::new(&exp, 0.0);
-        nodes.insert(0, start_node);
-        Simulation {
-            nodes,
-            t: 0.0,
-            exp,
-        }
-    }
+impl Sim for Simulation {
 
-    fn step(&mut self) -> bool{
-        let (key, min_t) = match self.nodes.iter()
-            .filter(|&(_,v)| v.state == State::Infected)
-            .map(|(k,v)| (k, v.get_min()))
-            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .map(|(&k, v)| (k, v))
-        {
-            Some(result) => result,
+    fn step(&mut self) -> bool {
+        let (key, min_t) = match self.next {
+            Some(next) => next,
             None => {
                 return false;
             }
@@ -53,44 +65,132 @@ impl Simulation {
                 match node.get_min_state() {
                     InfectProgress::Left => { self.infect(key - 1) },
                     InfectProgress::Right => { self.infect(key + 1) },
-                    _ => {}
+                    InfectProgress::Heal => { self.active.remove(key, 0) },
                 }
                 let node = self.nodes.get_mut(&key).unwrap();
-                node.step(&self.exp);
+                node.step(&self.exp, &mut self.rng);
             },
             None => {
                 panic!("Node not found");
             }
         }
+        self.next = self.next_event();
 
         true
     }
 
+    fn get_number_of_infected_nodes(&self) -> usize {
+        self.active.len()
+    }
+
+    fn get_t(&self) -> f64 {
+        self.t
+    }
+
+    fn get_next_t(&self) -> f64 {
+        self.next.map_or(f64::INFINITY, |(_, t)| t)
+    }
+
+    fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.active.bounding_box()
+    }
+
+    fn get_outbreak_stats(&self) -> OutbreakStats {
+        self.active.outbreak_stats(self.nodes.len())
+    }
+
+    fn get_squared_distance_sum(&self) -> f64 {
+        self.active.squared_distance_sum()
+    }
+
+    fn get_infected_sites(&self) -> Vec<(i32, i32)> {
+        self.nodes.iter().filter(|(_, v)| v.state == State::Infected).map(|(&k, _)| (k, 0)).collect()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut nodes: Vec<(i32, Node)> = self.nodes.iter().map(|(&k, v)| (k, v.clone())).collect();
+        nodes.sort_by_key(|&(k, _)| k);
+        Snapshot::Sim1d(SimulationState {
+            lambda: self.params.lambda(),
+            t: self.t,
+            nodes,
+            active: self.active.clone(),
+            rng: self.rng.clone(),
+        })
+    }
+}
+
+/// State of a run as stored in a `Snapshot`.
+#[derive(Serialize, Deserialize)]
+pub struct SimulationState {
+    lambda: f64,
+    t: f64,
+    nodes: Vec<(i32, Node)>,
+    active: ActiveSet,
+    rng: SimRng,
+}
+
+impl Simulation {
+    /// Starts with the node at the origin infected, the random numbers are drawn from a generator seeded with `seed`.
+    pub fn with_seed(params: ModelParams, seed: u64) -> Self {
+        let mut rng = SimRng::seed_from_u64(seed);
+        let mut nodes = HashMap::new();
+        let exp = Exp::new(params.lambda()).expect("lambda was validated");
+        let start_node = Node::new(&exp, 0.0, &mut rng);
+        nodes.insert(0, start_node);
+        let mut active = ActiveSet::new();
+        active.insert(0, 0, 0.0);
+        let mut sim = Simulation {
+            params,
+            nodes,
+            t: 0.0,
+            next: None,
+            exp,
+            active,
+            rng,
+        };
+        sim.next = sim.next_event();
+        sim
+    }
+
+    /// Continues a run from its state, the parameters are validated again.
+    pub fn restore(state: SimulationState) -> Result<Self, ParamError> {
+        let params = ModelParams::new(state.lambda)?;
+        let mut sim = Simulation {
+            params,
+            nodes: state.nodes.into_iter().collect(),
+            t: state.t,
+            next: None,
+            exp: Exp::new(params.lambda()).expect("lambda was validated"),
+            active: state.active,
+            rng: state.rng,
+        };
+        sim.next = sim.next_event();
+        Ok(sim)
+    }
+
+    /// Scans all infected nodes for the earliest event.
+    fn next_event(&self) -> Option<(i32, f64)> {
+        self.nodes.iter()
+            .filter(|&(_,v)| v.state == State::Infected)
+            .map(|(&k,v)| (k, v.get_min()))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+    }
+
     fn infect(&mut self, key: i32) {
         let node = self.nodes.get_mut(&key);
         match node {
             Some(node) => {
-                node.infect(&self.exp, self.t);
+                if node.state == State::Healthy {
+                    self.active.insert(key, 0, self.t);
+                }
+                node.infect(&self.exp, self.t, &mut self.rng);
             },
             None => {
-                let new_node = Node::new(&self.exp, self.t);
+                let new_node = Node::new(&self.exp, self.t, &mut self.rng);
                 self.nodes.insert(key, new_node);
+                self.active.insert(key, 0, self.t);
             }
         }
     }
-
-    pub fn run(&mut self, t_max: f64) -> bool {
-        while self.t < t_max {
-            let result = self.step();
-            if !result {
-                return true
-            }
-        }
-        false
-    }
-
-    pub fn get_number_of_infected_nodes(&self) -> usize {
-        self.nodes.iter().filter(|(_, v)| v.state == State::Infected).count()
-    }
 }
-
@@ -1,5 +1,6 @@
+use serde::{Deserialize, Serialize};
 
-#[derive(PartialEq, Eq)]
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum State {
     Infected,
     Healthy,
@@ -1,6 +1,8 @@
 
 use std::collections::HashMap;
+use rand::SeedableRng;
 use rand_distr::Exp;
+use serde::{Deserialize, Serialize};
 
 
 mod node;
@@ -12,58 +14,48 @@ use utils::State;
 use utils::InfectProgress;
 
 use super::sim::*;
-
-pub struct Simulation {
-    nodes: HashMap<i32, Node>,
-    t: f64,
-    exp_lr: Exp<f64>,
-    exp_two_lr: Exp<f64>
+use super::active_set::ActiveSet;
+use super::params::*;
+use super::snapshot::Snapshot;
+
+/// Validated parameters of the model, the nearest neighbours get lambda * alpha and the next nearest lambda * (1 - alpha).
+#[derive(Clone, Copy, Debug)]
+pub struct ModelParams {
+    lambda: f64,
+    alpha: f64,
 }
 
-impl Sim for Simulation {
-
-    fn run(&mut self, t_max: f64) -> bool {
-        while self.t < t_max {
-            let result = self.step();
-            if !result {
-                return true
-            }
-        }
-        false
+impl ModelParams {
+    pub fn new(lambda: f64, alpha: f64) -> Result<Self, ParamError> {
+        Ok(ModelParams { lambda: LAMBDA.check(lambda)?, alpha: ALPHA.check(alpha)? })
     }
 
-    fn get_number_of_infected_nodes(&self) -> usize {
-        self.nodes.iter().filter(|(_, v)| v.state == State::Infected).count()
+    pub fn lambda(&self) -> f64 {
+        self.lambda
     }
 
-
+    pub fn alpha(&self) -> f64 {
+        self.alpha
+    }
 }
 
+pub struct Simulation {
+    params: ModelParams,
+    nodes: HashMap<i32, Node>,
+    t: f64,
+    /// Node and time of the next event, found once per event by `next_event`.
+    next: Option<(i32, f64)>,
+    exp_lr: Exp<f64>,
+    exp_two_lr: Exp<f64>,
+    active: ActiveSet,
+    rng: SimRng,
+}
 
+impl Sim for Simulation {
 
-impl Simulation {
-
-    pub(crate) fn new(lambda: f64, alpha: f64) -> Self {
-        let mut nodes = HashMap::new();
-        let exp_lr = Exp::new(lambda * alpha).unwrap();
-        let exp_two_lr = Exp::new(lambda * (1.0 - alpha)).unwrap();
-        let start_node = Node::new(&exp_lr, &exp_two_lr, 0.0);
-        nodes.insert(0, start_node);
-        Simulation {
-            nodes,
-            t: 0.0,
-            exp_lr,
-            exp_two_lr,
-        }
-    }
-    fn step(&mut self) -> bool{
-        let (key, min_t) = match self.nodes.iter()
-            .filter(|&(_,v)| v.state == State::Infected)
-            .map(|(k,v)| (k, v.get_min()))
-            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
-            .map(|(&k, v)| (k, v))
-        {
-            Some(result) => result,
+    fn step(&mut self) -> bool {
+        let (key, min_t) = match self.next {
+            Some(next) => next,
             None => {
                 return false;
             }
@@ -79,28 +71,143 @@ impl Simulation {
                     InfectProgress::Right => { self.infect(key + 1) },
                     InfectProgress::TwoLeft => { self.infect(key - 2) },
                     InfectProgress::TwoRight => { self.infect(key + 2) },
-                    _ => {}
+                    InfectProgress::Heal => { self.active.remove(key, 0) },
                 }
                 let node = self.nodes.get_mut(&key).unwrap();
-                node.step(&self.exp_lr, &self.exp_two_lr);
+                node.step(&self.exp_lr, &self.exp_two_lr, &mut self.rng);
             },
             None => {
                 panic!("Node not found");
             }
         }
+        self.next = self.next_event();
 
         true
     }
 
+    fn get_number_of_infected_nodes(&self) -> usize {
+        self.active.len()
+    }
+
+    fn get_t(&self) -> f64 {
+        self.t
+    }
+
+    fn get_next_t(&self) -> f64 {
+        self.next.map_or(f64::INFINITY, |(_, t)| t)
+    }
+
+    fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.active.bounding_box()
+    }
+
+    fn get_outbreak_stats(&self) -> OutbreakStats {
+        self.active.outbreak_stats(self.nodes.len())
+    }
+
+    fn get_squared_distance_sum(&self) -> f64 {
+        self.active.squared_distance_sum()
+    }
+
+    fn get_infected_sites(&self) -> Vec<(i32, i32)> {
+        self.nodes.iter().filter(|(_, v)| v.state == State::Infected).map(|(&k, _)| (k, 0)).collect()
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        let mut nodes: Vec<(i32, Node)> = self.nodes.iter().map(|(&k, v)| (k, v.clone())).collect();
+        nodes.sort_by_key(|&(k, _)| k);
+        Snapshot::Sim1dB(SimulationState {
+            lambda: self.params.lambda(),
+            alpha: self.params.alpha(),
+            t: self.t,
+            nodes,
+            active: self.active.clone(),
+            rng: self.rng.clone(),
+        })
+    }
+
+
+}
+
+
+/// State of a run as stored in a `Snapshot`.
+#[derive(Serialize, Deserialize)]
+pub struct SimulationState {
+    lambda: f64,
+    alpha: f64,
+    t: f64,
+    nodes: Vec<(i32, Node)>,
+    active: ActiveSet,
+    rng: SimRng,
+}
+
+
+impl Simulation {
+
+    /// Starts with the node at the origin infected, the random numbers are drawn from a generator seeded with `seed`.
+    pub fn with_seed(params: ModelParams, seed: u64) -> Self {
+        let (lambda, alpha) = (params.lambda(), params.alpha());
+        let mut rng = SimRng::seed_from_u64(seed);
+        let mut nodes = HashMap::new();
+        let exp_lr = Exp::new(lambda * alpha).expect("parameters were validated");
+        let exp_two_lr = Exp::new(lambda * (1.0 - alpha)).expect("parameters were validated");
+        let start_node = Node::new(&exp_lr, &exp_two_lr, 0.0, &mut rng);
+        nodes.insert(0, start_node);
+        let mut active = ActiveSet::new();
+        active.insert(0, 0, 0.0);
+        let mut sim = Simulation {
+            params,
+            nodes,
+            t: 0.0,
+            next: None,
+            exp_lr,
+            exp_two_lr,
+            active,
+            rng,
+        };
+        sim.next = sim.next_event();
+        sim
+    }
+
+    /// Continues a run from its state, the parameters are validated again.
+    pub fn restore(state: SimulationState) -> Result<Self, ParamError> {
+        let params = ModelParams::new(state.lambda, state.alpha)?;
+        let (lambda, alpha) = (params.lambda(), params.alpha());
+        let mut sim = Simulation {
+            params,
+            nodes: state.nodes.into_iter().collect(),
+            t: state.t,
+            next: None,
+            exp_lr: Exp::new(lambda * alpha).expect("parameters were validated"),
+            exp_two_lr: Exp::new(lambda * (1.0 - alpha)).expect("parameters were validated"),
+            active: state.active,
+            rng: state.rng,
+        };
+        sim.next = sim.next_event();
+        Ok(sim)
+    }
+
+    /// Scans all infected nodes for the earliest event.
+    fn next_event(&self) -> Option<(i32, f64)> {
+        self.nodes.iter()
+            .filter(|&(_,v)| v.state == State::Infected)
+            .map(|(&k,v)| (k, v.get_min()))
+            .min_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap())
+    }
+
     fn infect(&mut self, key: i32) {
         let node = self.nodes.get_mut(&key);
         match node {
             Some(node) => {
-                node.infect(&self.exp_lr, &self.exp_two_lr, self.t);
+                if node.state == State::Healthy {
+                    self.active.insert(key, 0, self.t);
+                }
+                node.infect(&self.exp_lr, &self.exp_two_lr, self.t, &mut self.rng);
             },
             None => {
-                let new_node = Node::new(&self.exp_lr, &self.exp_two_lr, self.t);
+                let new_node = Node::new(&self.exp_lr, &self.exp_two_lr, self.t, &mut self.rng);
                 self.nodes.insert(key, new_node);
+                self.active.insert(key, 0, self.t);
             }
         }
     }
@@ -1,5 +1,7 @@
 use std::collections::HashMap;
+use rand::SeedableRng;
 use rand_distr::Exp;
+use serde::{Deserialize, Serialize};
 
 
 
@@ -14,6 +16,11 @@ use utils::State;
 
 use super::rbtree::RBTree;
 use super::rbtree::NodePtr;
+use super::sim::*;
+use super::active_set::ActiveSet;
+use super::params::ParamError;
+use super::snapshot::Snapshot;
+pub use super::sim1d::ModelParams;
 
 #[derive(Copy, Clone)]
 struct Value {
@@ -23,23 +30,15 @@ struct Value {
 }
 
 pub struct Simulation {
+    params: ModelParams,
     nodes: RBTree<Node, Value>,
     t: f64,
-    exp: Exp<f64>
+    exp: Exp<f64>,
+    active: ActiveSet,
+    rng: SimRng,
 }
 
-impl Simulation {
-    pub fn new(lambda: f64) -> Self {
-        let mut nodes = RBTree::new();
-        let exp = Exp::new(lambda).unwrap();
-        let start_node = Node::new(&exp, 0.0);
-        let _ = nodes.insert(start_node, Value { v: 0, left: NodePtr::null(), right: NodePtr::null() });
-        Simulation {
-            nodes,
-            t: 0.0,
-            exp,
-        }
-    }
+impl Sim for Simulation {
 
     fn step(&mut self) -> bool {
         let (option, ptr) = self.nodes.get_first_ptr();
@@ -59,7 +58,7 @@ impl Simulation {
         match node.get_min_state() {
             InfectProgress::Left => { value.left = self.infect_left(&value, ptr) },
             InfectProgress::Right => { value.right = self.infect_right(&value, ptr) },
-            _ => {}
+            InfectProgress::Heal => { self.active.remove(value.v, 0) },
         }
 
         self.node_step(&mut node, &value, ptr);
@@ -69,9 +68,112 @@ impl Simulation {
 
     }
 
+    fn get_number_of_infected_nodes(&self) -> usize {
+        self.active.len()
+    }
+
+    fn get_t(&self) -> f64 {
+        self.t
+    }
+
+    fn get_next_t(&self) -> f64 {
+        match self.nodes.get_first() {
+            Some((node, _)) if node.state == State::Infected => node.get_min(),
+            _ => f64::INFINITY,
+        }
+    }
+
+    fn get_bounding_box(&self) -> Option<BoundingBox> {
+        self.active.bounding_box()
+    }
+
+    fn get_outbreak_stats(&self) -> OutbreakStats {
+        self.active.outbreak_stats(self.nodes.len())
+    }
+
+    fn get_squared_distance_sum(&self) -> f64 {
+        self.active.squared_distance_sum()
+    }
+
+    fn get_infected_sites(&self) -> Vec<(i32, i32)> {
+        self.nodes.iter().filter(|(node, _)| node.state == State::Infected).map(|(_, value)| (value.v, 0)).collect()
+    }
+
+    //the neighbour pointers are not stored, the nodes are kept by position instead
+    fn snapshot(&self) -> Snapshot {
+        let mut nodes: Vec<(i32, Node)> = self.nodes.iter().map(|(node, value)| (value.v, *node)).collect();
+        nodes.sort_by_key(|&(v, _)| v);
+        Snapshot::Sim1dRb(SimulationState {
+            lambda: self.params.lambda(),
+            t: self.t,
+            nodes,
+            active: self.active.clone(),
+            rng: self.rng.clone(),
+        })
+    }
+}
+
+/// State of a run as stored in a `Snapshot`.
+#[derive(Serialize, Deserialize)]
+pub struct SimulationState {
+    lambda: f64,
+    t: f64,
+    nodes: Vec<(i32, Node)>,
+    active: ActiveSet,
+    rng: SimRng,
+}
+
+impl Simulation {
+    /// Starts with the node at the origin infected, the random numbers are drawn from a generator seeded with `seed`.
+    pub fn with_seed(params: ModelParams, seed: u64) -> Self {
+        let mut rng = SimRng::seed_from_u64(seed);
+        let mut nodes = RBTree::new();
+        let exp = Exp::new(params.lambda()).expect("lambda was validated");
+        let start_node = Node::new(&exp, 0.0, &mut rng);
+        let _ = nodes.insert(start_node, Value { v: 0, left: NodePtr::null(), right: NodePtr::null() });
+        let mut active = ActiveSet::new();
+        active.insert(0, 0, 0.0);
+        Simulation {
+            params,
+            nodes,
+            t: 0.0,
+            exp,
+            active,
+            rng,
+        }
+    }
+
+    /// Continues a run from its state, the parameters are validated again.
+    /// The stored sites form an interval, so neighbouring positions are linked.
+    pub fn restore(state: SimulationState) -> Result<Self, ParamError> {
+        let params = ModelParams::new(state.lambda)?;
+        let mut nodes = RBTree::new();
+        let mut previous: Option<(i32, NodePtr<Node, Value>)> = None;
+        for (v, node) in state.nodes {
+            let ptr = nodes.insert(node, Value { v, left: NodePtr::null(), right: NodePtr::null() });
+            if let Some((previous_v, previous_ptr)) = previous {
+                if previous_v + 1 == v {
+                    unsafe {
+                        (*ptr.0).value.left = previous_ptr;
+                        (*previous_ptr.0).value.right = ptr;
+                    }
+                }
+            }
+            previous = Some((v, ptr));
+        }
+        Ok(Simulation {
+            params,
+            nodes,
+            t: state.t,
+            exp: Exp::new(params.lambda()).expect("lambda was validated"),
+            active: state.active,
+            rng: state.rng,
+        })
+    }
+
     fn node_step(&mut self, node: &mut Node, value: &Value, ptr: NodePtr<Node, Value>) {
         unsafe {
-            node.step(&self.exp);
+            node.step(&self.exp, &mut self.rng);
             self.nodes.delete(ptr);
             let ptr = self.nodes.insert(*node, *value);
             if !value.left.is_null() {
@@ -89,9 +191,10 @@ impl Simulation {
 
     fn infect_left(&mut self, value: &Value, node_ptr: NodePtr<Node, Value>) -> NodePtr<Node, Value> {
         if value.left.is_null() {
-            let new_node = Node::new(&self.exp, self.t);
+            let new_node = Node::new(&self.exp, self.t, &mut self.rng);
             let new_value = Value { v: value.v - 1, left: NodePtr::null(), right: node_ptr };
             let new_ptr = self.nodes.insert(new_node, new_value);
+            self.active.insert(new_value.v, 0, self.t);
             new_ptr
         } else {
             unsafe {
@@ -99,10 +202,17 @@ impl Simulation {
                 let left_value = &(*value.left.0).value;
                 let mut c_left = Node { ..*left };
                 let c_left_value = Value { ..*left_value };
+                if c_left.state == State::Healthy {
+                    self.active.insert(c_left_value.v, 0, self.t);
+                }
 
-                c_left.infect(&self.exp, self.t);
+                c_left.infect(&self.exp, self.t, &mut self.rng);
                 self.nodes.delete(value.left);
                 let new_left_ptr = self.nodes.insert(c_left, c_left_value);
+                //the node moved, so the pointer of its own outer neighbour has to follow
+                if !c_left_value.left.is_null() {
+                    (*c_left_value.left.0).value.right = new_left_ptr;
+                }
                 new_left_ptr
             }
         }
@@ -111,9 +221,10 @@ impl Simulation {
 
     fn infect_right(&mut self, value: &Value, node_ptr: NodePtr<Node, Value>) -> NodePtr<Node, Value>{
         if value.right.is_null() {
-            let new_node = Node::new(&self.exp, self.t);
+            let new_node = Node::new(&self.exp, self.t, &mut self.rng);
             let new_value = Value { v: value.v + 1, left: node_ptr, right: NodePtr::null() };
             let new_ptr = self.nodes.insert(new_node, new_value);
+            self.active.insert(new_value.v, 0, self.t);
             new_ptr
         } else {
             unsafe {
@@ -121,28 +232,65 @@ impl Simulation {
                 let right_value = &(*value.right.0).value;
                 let mut c_right = Node { ..*right };
                 let c_right_value = Value { ..*right_value };
+                if c_right.state == State::Healthy {
+                    self.active.insert(c_right_value.v, 0, self.t);
+                }
 
-                c_right.infect(&self.exp, self.t);
+                c_right.infect(&self.exp, self.t, &mut self.rng);
                 self.nodes.delete(value.right);
                 let new_right_ptr = self.nodes.insert(c_right, c_right_value);
+                //the node moved, so the pointer of its own outer neighbour has to follow
+                if !c_right_value.right.is_null() {
+                    (*c_right_value.right.0).value.left = new_right_ptr;
+                }
                 new_right_ptr
             }
         }
     }
 
-    pub fn run(&mut self, t_max: f64) -> bool {
-        while self.t < t_max {
-            let result = self.step();
-            if !result {
-                return true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::ptr::addr_of;
+
+    use super::*;
+
+    //every neighbour pointer has to point at a node still in the tree, at the adjacent site,
+    //whose pointer back leads to the same node. Only the outermost sites lack a neighbour.
+    fn assert_linked(sim: &Simulation) {
+        let live: HashSet<*const Value> = sim.nodes.iter().map(|(_, value)| value as *const Value).collect();
+        let (min, max) = sim.nodes.iter().fold((i32::MAX, i32::MIN), |(min, max), (_, value)| (min.min(value.v), max.max(value.v)));
+        assert_eq!(sim.nodes.len(), (max - min + 1) as usize);
+        for (_, value) in sim.nodes.iter() {
+            for (neighbour, offset) in [(value.left, -1), (value.right, 1)] {
+                if neighbour.is_null() {
+                    assert_eq!(value.v, if offset < 0 { min } else { max });
+                    continue;
+                }
+                unsafe {
+                    assert!(live.contains(&addr_of!((*neighbour.0).value)), "site {} points at a freed node", value.v);
+                    let neighbour = &(*neighbour.0).value;
+                    assert_eq!(neighbour.v, value.v + offset);
+                    let back = if offset < 0 { neighbour.right } else { neighbour.left };
+                    assert_eq!(addr_of!((*back.0).value), value as *const Value);
+                }
             }
         }
-        false
     }
 
-    pub fn get_number_of_infected_nodes(&self) -> usize {
-        self.nodes.iter().filter(|(k, v)| k.state == State::Infected).count()
+    #[test]
+    fn test_neighbour_pointers() {
+        for seed in 0..20 {
+            let mut sim = Simulation::with_seed(ModelParams::new(3.0).unwrap(), seed);
+            assert_linked(&sim);
+            for _ in 0..2000 {
+                if !sim.step() {
+                    break;
+                }
+                assert_linked(&sim);
+            }
+        }
     }
-
 }
-
@@ -1,10 +1,12 @@
+use rand::Rng;
 use rand::distributions::Distribution;
 use rand_distr::{Exp, Exp1};
+use serde::{Deserialize, Serialize};
 
 use super::utils::*;
 
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Serialize, Deserialize)]
 pub struct Node {
     pub t_heal: f64,
     pub t_infect_left: f64,
@@ -14,19 +16,19 @@ pub struct Node {
 
 
 impl Node {
-    pub fn new(exp: &Exp<f64>, t: f64) -> Self {
+    pub fn new<R: Rng>(exp: &Exp<f64>, t: f64, rng: &mut R) -> Self {
         let mut t_heal = -1.0;
         let mut t_infect_left = -1.0;
         let mut t_infect_right = -1.0;
         while t_heal < t {
-            let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+            let random_number: f64 = Exp1.sample(rng);
             t_heal += random_number
         }
         while t_infect_left < t {
-            t_infect_left += exp.sample(&mut rand::thread_rng());
+            t_infect_left += exp.sample(rng);
         }
         while t_infect_right < t {
-            t_infect_right += exp.sample(&mut rand::thread_rng());
+            t_infect_right += exp.sample(rng);
         }
 
         Node {
@@ -57,43 +59,43 @@ impl Node {
         }
     }
 
-    fn heal(&mut self, exp: &Exp<f64>) {
+    fn heal<R: Rng>(&mut self, exp: &Exp<f64>, rng: &mut R) {
         while self.t_infect_left < self.t_heal {
-            self.t_infect_left += exp.sample(&mut rand::thread_rng());
+            self.t_infect_left += exp.sample(rng);
         }
         while self.t_infect_right < self.t_heal {
-            self.t_infect_right += exp.sample(&mut rand::thread_rng());
+            self.t_infect_right += exp.sample(rng);
         }
 
-        let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+        let random_number: f64 = Exp1.sample(rng);
         self.t_heal += random_number;
         self.state = State::Healthy;
     }
 
-    pub fn infect(&mut self, exp: &Exp<f64>, t: f64) {
+    pub fn infect<R: Rng>(&mut self, exp: &Exp<f64>, t: f64, rng: &mut R) {
         self.state = State::Infected;
         while self.t_heal < t {
-            let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+            let random_number: f64 = Exp1.sample(rng);
             self.t_heal += random_number;
         }
         while self.t_infect_left < t {
-            self.t_infect_left += exp.sample(&mut rand::thread_rng());
+            self.t_infect_left += exp.sample(rng);
         }
         while self.t_infect_right < t {
-            self.t_infect_right += exp.sample(&mut rand::thread_rng());
+            self.t_infect_right += exp.sample(rng);
         }
     }
 
-    pub fn step(&mut self, exp: &Exp<f64>) {
+    pub fn step<R: Rng>(&mut self, exp: &Exp<f64>, rng: &mut R) {
         match self.get_min_state() {
             InfectProgress::Left => {
-                self.t_infect_left += exp.sample(&mut rand::thread_rng());
+                self.t_infect_left += exp.sample(rng);
             },
             InfectProgress::Right => {
-                self.t_infect_right += exp.sample(&mut rand::thread_rng());
+                self.t_infect_right += exp.sample(rng);
             },
             InfectProgress::Heal => {
-                self.heal(exp);
+                self.heal(exp, rng);
             }
         }
     }
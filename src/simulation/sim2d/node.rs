@@ -1,8 +1,11 @@
+use rand::Rng;
 use rand::distributions::Distribution;
 use rand_distr::{Exp, Exp1};
+use serde::{Deserialize, Serialize};
 
 use super::utils::*;
 
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Node {
     t_heal: f64,
     t_infect_left: f64,
@@ -13,30 +16,30 @@ pub struct Node {
 }
 
 impl Node {
-    pub fn new(exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, t: f64) -> Self {
+    pub fn new<R: Rng>(exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, t: f64, rng: &mut R) -> Self {
         let mut t_heal = -1.0;
         let mut t_infect_left = -1.0;
         let mut t_infect_right = -1.0;
         let mut t_infect_up = -1.0;
         let mut t_infect_down = -1.0;
         while t_heal < t {
-            let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+            let random_number: f64 = Exp1.sample(rng);
             t_heal += random_number
         }
         while t_infect_left < t {
-            t_infect_left += exp_lr.sample(&mut rand::thread_rng());
+            t_infect_left += exp_lr.sample(rng);
         }
 
         while t_infect_right < t {
-            t_infect_right += exp_lr.sample(&mut rand::thread_rng());
+            t_infect_right += exp_lr.sample(rng);
         }
 
         while t_infect_up < t {
-            t_infect_up += exp_ud.sample(&mut rand::thread_rng());
+            t_infect_up += exp_ud.sample(rng);
         }
 
         while t_infect_down < t {
-            t_infect_down += exp_ud.sample(&mut rand::thread_rng());
+            t_infect_down += exp_ud.sample(rng);
         }
 
         Node {
@@ -76,52 +79,52 @@ impl Node {
     }
 
 
-    fn heal(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>) {
+    fn heal<R: Rng>(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, rng: &mut R) {
         while self.t_infect_left < self.t_heal {
-            self.t_infect_left += exp_lr.sample(&mut rand::thread_rng());
+            self.t_infect_left += exp_lr.sample(rng);
         }
         while self.t_infect_right < self.t_heal {
-            self.t_infect_right += exp_lr.sample(&mut rand::thread_rng());
+            self.t_infect_right += exp_lr.sample(rng);
         }
         while self.t_infect_up < self.t_heal {
-            self.t_infect_up += exp_ud.sample(&mut rand::thread_rng());
+            self.t_infect_up += exp_ud.sample(rng);
         }
         while self.t_infect_down < self.t_heal {
-            self.t_infect_down += exp_ud.sample(&mut rand::thread_rng());
+            self.t_infect_down += exp_ud.sample(rng);
         }
 
-        let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+        let random_number: f64 = Exp1.sample(rng);
         self.t_heal += random_number;
         self.state = State::Healthy;
     }
 
-    pub fn infect(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, t: f64) {
+    pub fn infect<R: Rng>(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, t: f64, rng: &mut R) {
         self.state = State::Infected;
         while self.t_heal < t {
-            let random_number: f64 = Exp1.sample(&mut rand::thread_rng());
+            let random_number: f64 = Exp1.sample(rng);
             self.t_heal += random_number;
         }
         while self.t_infect_left < t {
-            self.t_infect_left += exp_lr.sample(&mut rand::thread_rng());
+            self.t_infect_left += exp_lr.sample(rng);
         }
         while self.t_infect_right < t {
-            self.t_infect_right += exp_lr.sample(&mut rand::thread_rng());
+            self.t_infect_right += exp_lr.sample(rng);
         }
         while self.t_infect_up < t {
-            self.t_infect_up += exp_ud.sample(&mut rand::thread_rng());
+            self.t_infect_up += exp_ud.sample(rng);
         }
         while self.t_infect_down < t {
-            self.t_infect_down += exp_ud.sample(&mut rand::thread_rng());
+            self.t_infect_down += exp_ud.sample(rng);
         }
     }
 
-    pub fn step(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>) {
+    pub fn step<R: Rng>(&mut self, exp_lr: &Exp<f64>, exp_ud: &Exp<f64>, rng: &mut R) {
         match self.get_min_state() {
-            InfectProgress::Heal => { self.heal(exp_lr, exp_ud) },
-            InfectProgress::Left => { self.t_infect_left += exp_lr.sample(&mut rand::thread_rng())},
-            InfectProgress::Right => { self.t_infect_right += exp_lr.sample(&mut rand::thread_rng())},
-            InfectProgress::Up => { self.t_infect_up += exp_ud.sample(&mut rand::thread_rng())},
-            InfectProgress::Down => { self.t_infect_down += exp_ud.sample(&mut rand::thread_rng())},
+            InfectProgress::Heal => { self.heal(exp_lr, exp_ud, rng) },
+            InfectProgress::Left => { self.t_infect_left += exp_lr.sample(rng)},
+            InfectProgress::Right => { self.t_infect_right += exp_lr.sample(rng)},
+            InfectProgress::Up => { self.t_infect_up += exp_ud.sample(rng)},
+            InfectProgress::Down => { self.t_infect_down += exp_ud.sample(rng)},
         }
     }
 }
\ No newline at end of file
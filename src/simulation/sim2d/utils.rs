@@ -1,4 +1,6 @@
-#[derive(PartialEq, Eq)]
+use serde::{Deserialize, Serialize};
+
+#[derive(PartialEq, Eq, Clone, Copy, Serialize, Deserialize)]
 pub enum State {
     Infected,
     Healthy,
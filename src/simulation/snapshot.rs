@@ -0,0 +1,89 @@
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter};
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use super::params::ParamError;
+use super::sim::Sim;
+use super::{finite, sim1d, sim1d_b, sim1d_rb, sim2d};
+
+/// Full state of a running engine including its random number generator, a restored
+/// engine produces exactly the events the original would have produced.
+/// Files are MessagePack since clocks of a direction with rate zero are infinite, which JSON can't hold.
+#[derive(Serialize, Deserialize)]
+pub enum Snapshot {
+    Sim1d(sim1d::SimulationState),
+    Sim1dRb(sim1d_rb::SimulationState),
+    Sim1dB(sim1d_b::SimulationState),
+    Sim2d(sim2d::SimulationState),
+    Finite(finite::Simulation),
+}
+
+impl Snapshot {
+    /// Rebuilds the engine, the model parameters are validated again.
+    pub fn restore(self) -> Result<Box<dyn Sim>, ParamError> {
+        Ok(match self {
+            Snapshot::Sim1d(snapshot) => Box::new(sim1d::Simulation::restore(snapshot)?),
+            Snapshot::Sim1dRb(snapshot) => Box::new(sim1d_rb::Simulation::restore(snapshot)?),
+            Snapshot::Sim1dB(snapshot) => Box::new(sim1d_b::Simulation::restore(snapshot)?),
+            Snapshot::Sim2d(snapshot) => Box::new(sim2d::Simulation::restore(snapshot)?),
+            Snapshot::Finite(sim) => Box::new(sim),
+        })
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        rmp_serde::encode::write(&mut writer, self).map_err(io::Error::other)
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let reader = BufReader::new(File::open(path)?);
+        rmp_serde::decode::from_read(reader).map_err(io::Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::registry::test_models;
+
+    //the original and the restored engine have to agree event by event
+    fn assert_identical_continuation(mut original: Box<dyn Sim>, t_pause: f64, t_max: f64) {
+        original.run(t_pause);
+        let path = std::env::temp_dir().join(format!("snapshot_{}.msgpack", std::process::id()));
+        original.snapshot().save(&path).unwrap();
+        let mut restored = Snapshot::load(&path).unwrap().restore().unwrap();
+        std::fs::remove_file(&path).unwrap();
+
+        assert_eq!(original.get_t(), restored.get_t());
+        loop {
+            let alive = original.step();
+            assert_eq!(alive, restored.step());
+            assert_eq!(original.get_t(), restored.get_t());
+            assert_eq!(original.get_number_of_infected_nodes(), restored.get_number_of_infected_nodes());
+            assert_eq!(original.get_bounding_box(), restored.get_bounding_box());
+            if !alive || original.get_t() > t_max {
+                break;
+            }
+        }
+        assert_eq!(original.get_outbreak_stats().infections, restored.get_outbreak_stats().infections);
+    }
+
+    #[test]
+    fn test_restore() {
+        //alpha = 1 makes one direction infinitely slow
+        for alpha in [0.5, 1.0] {
+            for (model, values) in test_models(2.5, alpha) {
+                //the first seed which survives until the pause
+                let sim = (0..).map(|seed| model.create_with_seed(&values, seed).unwrap())
+                    .find_map(|mut sim| if sim.run(5.0) { None } else { Some(sim) })
+                    .unwrap();
+                assert_identical_continuation(sim, 5.0, 10.0);
+            }
+        }
+        let mut sim = finite::Simulation::with_seed(8, 2, 1.5, 0.5, 3);
+        sim.run(1.0);
+        assert_identical_continuation(Box::new(sim), 2.0, 4.0);
+    }
+}
@@ -0,0 +1,111 @@
+//! Statistical checks that the engines simulate the same process. Every run is seeded,
+//! so the tests are reproducible, and the samples are compared with chi-square and
+//! Kolmogorov-Smirnov tests.
+
+use crate::analysis::stats::{chi_square_two_sample, ks_two_sample};
+
+use super::sim::Sim;
+use super::{sim1d, sim1d_b, sim1d_rb, sim2d};
+
+/// Smallest accepted p-value of a single test.
+const P_MIN: f64 = 1e-3;
+
+const RUNS: u64 = 1000;
+
+/// Directed percolation survival exponent in 1D.
+const DELTA_1D: f64 = 0.1595;
+
+const LAMBDA_C_1D: f64 = 1.6489;
+
+struct Sample {
+    died: usize,
+    /// Extinction time, or t_max for runs that survived.
+    times: Vec<f64>,
+    end_nodes: Vec<f64>,
+}
+
+fn sample<F: Fn(u64) -> Box<dyn Sim>>(new_sim: F, first_seed: u64, t_max: f64) -> Sample {
+    let mut sample = Sample { died: 0, times: Vec::new(), end_nodes: Vec::new() };
+    for seed in first_seed..first_seed + RUNS {
+        let mut sim = new_sim(seed);
+        let died = sim.run(t_max);
+        if died {
+            sample.died += 1;
+        }
+        sample.times.push(if died { sim.get_t() } else { t_max });
+        sample.end_nodes.push(sim.get_number_of_infected_nodes() as f64);
+    }
+    sample
+}
+
+fn assert_same_distribution(a: &Sample, b: &Sample) {
+    let runs = RUNS as usize;
+    let (_, p) = chi_square_two_sample(&[a.died, runs - a.died], &[b.died, runs - b.died]);
+    assert!(p > P_MIN, "survival differs: {} and {} of {} died, p = {}", a.died, b.died, runs, p);
+    let (distance, p) = ks_two_sample(&a.times, &b.times);
+    assert!(p > P_MIN, "extinction times differ: D = {}, p = {}", distance, p);
+    let (distance, p) = ks_two_sample(&a.end_nodes, &b.end_nodes);
+    assert!(p > P_MIN, "end nodes differ: D = {}, p = {}", distance, p);
+}
+
+#[test]
+fn test_sim1d_matches_sim1d_rb() {
+    let lambda = 1.8;
+    let a = sample(|seed| Box::new(sim1d::Simulation::with_seed(sim1d::ModelParams::new(lambda).unwrap(), seed)), 0, 20.0);
+    let b = sample(|seed| Box::new(sim1d_rb::Simulation::with_seed(sim1d_rb::ModelParams::new(lambda).unwrap(), seed)), RUNS, 20.0);
+    assert_same_distribution(&a, &b);
+}
+
+#[test]
+fn test_sim1d_b_reduces_to_sim1d() {
+    let lambda = 1.8;
+    let a = sample(|seed| Box::new(sim1d::Simulation::with_seed(sim1d::ModelParams::new(lambda).unwrap(), seed)), 0, 20.0);
+    let b = sample(|seed| Box::new(sim1d_b::Simulation::with_seed(sim1d_b::ModelParams::new(lambda, 1.0).unwrap(), seed)), RUNS, 20.0);
+    assert_same_distribution(&a, &b);
+}
+
+#[test]
+fn test_sim2d_reduces_to_sim1d() {
+    let lambda = 1.8;
+    let a = sample(|seed| Box::new(sim1d::Simulation::with_seed(sim1d::ModelParams::new(lambda).unwrap(), seed)), 0, 20.0);
+    let b = sample(|seed| Box::new(sim2d::Simulation::with_seed(sim2d::ModelParams::new(lambda, 1.0).unwrap(), seed)), RUNS, 20.0);
+    assert_same_distribution(&a, &b);
+
+    // without vertical infections the outbreak never leaves the row y = 0
+    for seed in 0..100 {
+        let mut sim = sim2d::Simulation::with_seed(sim2d::ModelParams::new(lambda, 1.0).unwrap(), seed);
+        while sim.get_t() < 20.0 && sim.step() {
+            if let Some(bounding_box) = sim.get_bounding_box() {
+                assert_eq!((bounding_box.min_y, bounding_box.max_y), (0, 0));
+            }
+        }
+    }
+}
+
+/// Effective survival exponent between t_max / 8 and t_max from single seed runs.
+fn effective_delta(lambda: f64, first_seed: u64, t_max: f64) -> f64 {
+    let times = [t_max / 8.0, t_max];
+    let mut surviving = [0; 2];
+    for seed in first_seed..first_seed + 4 * RUNS {
+        let mut sim = sim1d_rb::Simulation::with_seed(sim1d_rb::ModelParams::new(lambda).unwrap(), seed);
+        for (k, &t) in times.iter().enumerate() {
+            if !sim.run(t) {
+                surviving[k] += 1;
+            }
+        }
+    }
+    (surviving[0] as f64 / surviving[1] as f64).ln() / 8f64.ln()
+}
+
+/// At lambda_c the survival probability decays with the directed percolation exponent,
+/// faster below and slower above. Interpolating where the effective exponent crosses it
+/// gives lambda_c.
+#[test]
+fn test_critical_point_1d() {
+    let (below, above) = (1.625, 1.675);
+    let delta_below = effective_delta(below, 0, 200.0);
+    let delta_above = effective_delta(above, 4 * RUNS, 200.0);
+    assert!(delta_below > DELTA_1D && delta_above < DELTA_1D, "{} {}", delta_below, delta_above);
+    let lambda_c = below + (delta_below - DELTA_1D) * (above - below) / (delta_below - delta_above);
+    assert!((lambda_c - LAMBDA_C_1D).abs() < 0.02, "lambda_c = {}", lambda_c);
+}
@@ -0,0 +1,249 @@
+use std::io;
+use std::path::Path;
+
+use rusqlite::types::Value;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+
+use crate::manifest::Manifest;
+use crate::output::{Field, RecordWriter};
+use crate::replica::Replica;
+
+/// Every result set is a row of `runs` holding its manifest, its rows are stored in long format
+/// in `results` with one entry per column of the result file, keyed by the CSV header.
+/// All 1D runs with lambda in [1.6, 1.7]:
+///
+/// ```sql
+/// SELECT runs.model, lambda.value, died.value FROM runs
+/// JOIN results lambda ON lambda.run = runs.id AND lambda.name = 'Lambda'
+/// JOIN results died ON died.run = runs.id AND died.row = lambda.row AND died.name = 'Disease died average'
+/// WHERE runs.model LIKE 'sim1d%' AND lambda.value BETWEEN 1.6 AND 1.7
+/// ```
+///
+/// Per-replica records of a sweep are a run of kind `replicas` with their rows in `replicas`.
+/// Seeds and other integers beyond i64 are stored as the i64 with the same bits, flags as 0 or 1,
+/// parameters and failures as JSON.
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY,
+    kind TEXT NOT NULL,
+    sweep TEXT NOT NULL,
+    model TEXT,
+    parameters TEXT NOT NULL,
+    seed INTEGER,
+    git_commit TEXT,
+    git_dirty INTEGER,
+    host TEXT,
+    started INTEGER NOT NULL,
+    wall_time REAL NOT NULL,
+    failures TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS results (
+    run INTEGER NOT NULL REFERENCES runs(id),
+    row INTEGER NOT NULL,
+    name TEXT NOT NULL,
+    value,
+    PRIMARY KEY (run, row, name)
+);
+CREATE INDEX IF NOT EXISTS results_by_name ON results(name, value);
+CREATE TABLE IF NOT EXISTS replicas (
+    run INTEGER NOT NULL REFERENCES runs(id),
+    lambda REAL NOT NULL,
+    alpha REAL,
+    replica INTEGER NOT NULL,
+    seed INTEGER NOT NULL,
+    died INTEGER NOT NULL,
+    extinction_time REAL,
+    final_infected INTEGER NOT NULL,
+    max_infected INTEGER NOT NULL,
+    events INTEGER NOT NULL
+);
+CREATE INDEX IF NOT EXISTS replicas_by_point ON replicas(run, lambda, alpha);
+";
+
+/// `.sqlite` and `.db` outputs go to a store instead of a file.
+pub fn is_database(path: &str) -> bool {
+    matches!(Path::new(path).extension().and_then(|e| e.to_str()), Some("sqlite") | Some("db"))
+}
+
+pub struct Store {
+    connection: Connection,
+}
+
+impl Store {
+    /// Opens the database at `path`, creating it and its tables if needed.
+    pub fn open<P: AsRef<Path>>(path: P) -> rusqlite::Result<Self> {
+        let connection = Connection::open(path)?;
+        connection.execute_batch(SCHEMA)?;
+        Ok(Store { connection })
+    }
+
+    /// Adds a result set, `kind` tells the result types apart, e.g. `simulation` or `extinction`.
+    /// The run and its rows are committed together by `flush` of the writer,
+    /// a writer dropped before rolls them back.
+    pub fn add_run(&mut self, kind: &str, manifest: &Manifest) -> rusqlite::Result<ResultsWriter<'_>> {
+        let (transaction, run) = self.insert_run(kind, manifest)?;
+        Ok(ResultsWriter { transaction: Some(transaction), run, header: Vec::new(), row: 0 })
+    }
+
+    /// Adds the run of a replica stream, the replicas are committed together by `commit`.
+    pub fn add_replicas(&mut self, manifest: &Manifest) -> rusqlite::Result<ReplicaInserter<'_>> {
+        //the run is written like any result set, just without rows in results
+        let (transaction, run) = self.insert_run("replicas", manifest)?;
+        Ok(ReplicaInserter { transaction, run })
+    }
+
+    fn insert_run(&mut self, kind: &str, manifest: &Manifest) -> rusqlite::Result<(Transaction<'_>, i64)> {
+        let transaction = Transaction::new(&mut self.connection, TransactionBehavior::Deferred)?;
+        transaction.execute(
+            "INSERT INTO runs (kind, sweep, model, parameters, seed, git_commit, git_dirty, host, started, wall_time, failures)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                kind,
+                manifest.sweep,
+                manifest.model,
+                serde_json::to_string(&manifest.parameters).expect("parameters are plain values"),
+                manifest.seed.map(|seed| seed as i64),
+                manifest.git_commit,
+                manifest.git_dirty,
+                manifest.host,
+                manifest.started as i64,
+                manifest.wall_time,
+                serde_json::to_string(&manifest.failures).expect("failures are strings"),
+            ])?;
+        let run = transaction.last_insert_rowid();
+        Ok((transaction, run))
+    }
+}
+
+pub struct ReplicaInserter<'a> {
+    transaction: Transaction<'a>,
+    run: i64,
+}
+
+impl ReplicaInserter<'_> {
+    pub fn insert(&mut self, replica: &Replica) -> rusqlite::Result<()> {
+        self.transaction.prepare_cached(
+            "INSERT INTO replicas (run, lambda, alpha, replica, seed, died, extinction_time, final_infected, max_infected, events)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)")?
+            .execute(params![
+                self.run,
+                replica.lambda,
+                replica.alpha,
+                replica.replica as i64,
+                replica.seed as i64,
+                replica.died,
+                replica.extinction_time,
+                replica.final_infected as i64,
+                replica.max_infected as i64,
+                replica.events as i64,
+            ])?;
+        Ok(())
+    }
+
+    /// The wall time of the run is taken from the finished manifest.
+    pub fn commit(self, manifest: &Manifest) -> rusqlite::Result<()> {
+        self.transaction.execute("UPDATE runs SET wall_time = ?1 WHERE id = ?2", params![manifest.wall_time, self.run])?;
+        self.transaction.commit()
+    }
+}
+
+pub struct ResultsWriter<'a> {
+    /// Taken by the first `flush`.
+    transaction: Option<Transaction<'a>>,
+    run: i64,
+    header: Vec<String>,
+    row: i64,
+}
+
+impl RecordWriter for ResultsWriter<'_> {
+    fn write_header(&mut self, header: &[&str]) -> io::Result<()> {
+        self.header = header.iter().map(|h| h.to_string()).collect();
+        Ok(())
+    }
+
+    //fields without a header are keyed by their position
+    fn write_record(&mut self, record: &[String]) -> io::Result<()> {
+        let transaction = self.transaction.as_ref().ok_or_else(|| io::Error::other("results were already committed"))?;
+        let mut insert = transaction
+            .prepare_cached("INSERT INTO results (run, row, name, value) VALUES (?1, ?2, ?3, ?4)")
+            .map_err(io::Error::other)?;
+        for (i, field) in record.iter().enumerate() {
+            let name = self.header.get(i).cloned().unwrap_or_else(|| i.to_string());
+            let value = match Field::parse(field) {
+                Field::Null => Value::Null,
+                Field::Bool(boolean) => Value::Integer(boolean as i64),
+                Field::Integer(integer) => Value::Integer(integer),
+                Field::Unsigned(unsigned) => Value::Integer(unsigned as i64),
+                Field::Real(real) => Value::Real(real),
+                Field::Text(text) => Value::Text(text.to_string()),
+            };
+            insert.execute(params![self.run, self.row, name, value]).map_err(io::Error::other)?;
+        }
+        self.row += 1;
+        Ok(())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self.transaction.take() {
+            Some(transaction) => transaction.commit().map_err(io::Error::other),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_store() {
+        let mut store = Store::open(":memory:").unwrap();
+        for (model, lambdas) in [("sim1d", ["1.5", "1.65"]), ("sim2d", ["1.6", "1.7"])] {
+            let manifest = Manifest::new("run_simulation").model(model).seed(u64::MAX);
+            let mut wtr = store.add_run("simulation", &manifest).unwrap();
+            wtr.write_header(&["Lambda", "Disease died average"]).unwrap();
+            for lambda in lambdas {
+                wtr.write_record(&[lambda.to_string(), "0.25".to_string()]).unwrap();
+            }
+            wtr.flush().unwrap();
+            wtr.flush().unwrap();
+        }
+
+        //a writer dropped before flushing leaves nothing behind and the next run still commits
+        let mut wtr = store.add_run("simulation", &Manifest::new("run_simulation").model("sim3d")).unwrap();
+        wtr.write_header(&["Lambda"]).unwrap();
+        wtr.write_record(&["1.5".to_string()]).unwrap();
+        drop(wtr);
+        let runs: i64 = store.connection.query_row("SELECT COUNT(*) FROM runs WHERE model = 'sim3d'", [], |row| row.get(0)).unwrap();
+        assert_eq!(runs, 0);
+
+        let selected: Vec<(String, f64)> = store.connection.prepare(
+            "SELECT runs.model, lambda.value FROM runs
+             JOIN results lambda ON lambda.run = runs.id AND lambda.name = 'Lambda'
+             JOIN results died ON died.run = runs.id AND died.row = lambda.row AND died.name = 'Disease died average'
+             WHERE runs.model LIKE 'sim1d%' AND lambda.value BETWEEN 1.6 AND 1.7 AND died.value = 0.25").unwrap()
+            .query_map([], |row| Ok((row.get(0)?, row.get(1)?))).unwrap()
+            .collect::<rusqlite::Result<_>>().unwrap();
+        assert_eq!(selected, [("sim1d".to_string(), 1.65)]);
+
+        let seed: i64 = store.connection.query_row("SELECT seed FROM runs LIMIT 1", [], |row| row.get(0)).unwrap();
+        assert_eq!(seed as u64, u64::MAX);
+
+        let manifest = Manifest::new("run_simulation_alpha").model("sim2d");
+        let mut replicas = store.add_replicas(&manifest).unwrap();
+        for (replica, died) in [(0, true), (1, false), (2, true)] {
+            replicas.insert(&Replica {
+                lambda: 1.65, alpha: Some(0.5), replica, seed: u64::MAX - replica as u64, died,
+                extinction_time: if died { Some(3.5) } else { None },
+                final_infected: if died { 0 } else { 12 }, max_infected: 20, events: 100,
+            }).unwrap();
+        }
+        replicas.commit(&manifest.finished()).unwrap();
+        let (died, seed): (i64, i64) = store.connection.query_row(
+            "SELECT SUM(replicas.died), MIN(replicas.seed) FROM replicas JOIN runs ON runs.id = replicas.run
+             WHERE runs.kind = 'replicas' AND runs.model = 'sim2d' AND replicas.alpha = 0.5", [],
+            |row| Ok((row.get(0)?, row.get(1)?))).unwrap();
+        assert_eq!(died, 2);
+        assert_eq!(seed as u64, u64::MAX - 2);
+    }
+}
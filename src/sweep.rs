@@ -0,0 +1,204 @@
+use std::any::Any;
+use std::fmt::Display;
+use std::io;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::mpsc::Sender;
+
+use threadpool::ThreadPool;
+
+use crate::checkpoint::CheckpointError;
+use crate::manifest::Manifest;
+use crate::simulation::params::ParamError;
+
+/// Why a grid point has no result.
+#[derive(Clone, Debug)]
+pub enum FailureReason {
+    InvalidParameters(ParamError),
+    /// The worker panicked, holds the panic message.
+    Panicked(String),
+}
+
+/// A grid point without a result, `point` describes its parameters.
+#[derive(Debug)]
+pub struct Failure {
+    pub point: String,
+    pub reason: FailureReason,
+}
+
+impl Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.reason {
+            FailureReason::InvalidParameters(error) => write!(f, "{}: invalid parameters, {}", self.point, error),
+            FailureReason::Panicked(message) => write!(f, "{}: worker panicked, {}", self.point, message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum SweepError {
+    UnknownModel(String),
+    /// A parameter shared by the whole sweep is invalid, nothing was run.
+    InvalidParameter(ParamError),
+    Output(io::Error),
+    Checkpoint(CheckpointError),
+    /// Some grid points failed, the results of the others were still written.
+    Incomplete(Vec<Failure>),
+}
+
+impl Display for SweepError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SweepError::UnknownModel(message) => write!(f, "{}", message),
+            SweepError::InvalidParameter(error) => write!(f, "invalid parameter, {}", error),
+            SweepError::Output(error) => write!(f, "could not write results, {}", error),
+            SweepError::Checkpoint(error) => write!(f, "{}", error),
+            SweepError::Incomplete(failures) => {
+                write!(f, "{} grid points failed", failures.len())?;
+                for failure in failures {
+                    write!(f, "\n  {}", failure)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for SweepError {}
+
+impl From<io::Error> for SweepError {
+    fn from(error: io::Error) -> Self {
+        SweepError::Output(error)
+    }
+}
+
+impl From<CheckpointError> for SweepError {
+    fn from(error: CheckpointError) -> Self {
+        SweepError::Checkpoint(error)
+    }
+}
+
+impl From<ParamError> for SweepError {
+    fn from(error: ParamError) -> Self {
+        SweepError::InvalidParameter(error)
+    }
+}
+
+/// Results of the grid points that succeeded and the grid points that failed.
+pub struct Sweep<R> {
+    pub results: Vec<R>,
+    pub failures: Vec<Failure>,
+    pub manifest: Manifest,
+}
+
+impl<R> Sweep<R> {
+    pub fn new(manifest: Manifest) -> Self {
+        Sweep { results: Vec::new(), failures: Vec::new(), manifest }
+    }
+
+    /// The manifest including the failed grid points, the wall time is taken now.
+    pub fn finished_manifest(&self) -> Manifest {
+        let mut manifest = self.manifest.finished();
+        manifest.failures = self.failures.iter().map(|f| f.to_string()).collect();
+        manifest
+    }
+
+    /// Records a failed grid point and reports it right away.
+    pub fn fail(&mut self, point: String, reason: FailureReason) {
+        let failure = Failure { point, reason };
+        eprintln!("{}", failure);
+        self.failures.push(failure);
+    }
+
+    /// To be called once the results are written, fails if any grid point did.
+    pub fn finish(self) -> Result<(), SweepError> {
+        if self.failures.is_empty() {
+            Ok(())
+        } else {
+            Err(SweepError::Incomplete(self.failures))
+        }
+    }
+}
+
+//splitmix64 finalizer
+fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+    z ^ (z >> 31)
+}
+
+/// Seed of replica `replica` at the grid point with the parameter values `point`. Depends on the
+/// values instead of the position in the grid, so sweeps over overlapping ranges and resumed
+/// sweeps reproduce the same replicas.
+pub fn replica_seed(seed: u64, point: &[f64], replica: usize) -> u64 {
+    let mut z = mix(seed);
+    for value in point {
+        z = mix(z ^ value.to_bits());
+    }
+    mix(z ^ replica as u64)
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic".to_string()
+    }
+}
+
+/// Runs `job` on the pool and sends its result together with `key`.
+/// A panic is caught and sent as the error instead of silently losing the grid point.
+pub fn execute<K, T, F>(pool: &ThreadPool, tx: &Sender<(K, Result<T, String>)>, key: K, job: F)
+    where K: Send + 'static, T: Send + 'static, F: FnOnce() -> T + Send + 'static
+{
+    let tx = tx.clone();
+    pool.execute(move || {
+        let result = catch_unwind(AssertUnwindSafe(job)).map_err(panic_message);
+        tx.send((key, result)).unwrap();
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn test_execute_catches_panics() {
+        let pool = ThreadPool::new(2);
+        let (tx, rx) = mpsc::channel();
+        for key in 0..4 {
+            execute(&pool, &tx, key, move || {
+                if key == 2 {
+                    panic!("grid point {} failed", key);
+                }
+                key * 10
+            });
+        }
+        pool.join();
+        drop(tx);
+
+        let mut sweep = Sweep::new(Manifest::new("test"));
+        for (key, result) in rx {
+            match result {
+                Ok(value) => sweep.results.push(value),
+                Err(message) => sweep.fail(key.to_string(), FailureReason::Panicked(message)),
+            }
+        }
+        sweep.results.sort();
+        assert_eq!(sweep.results, vec![0, 10, 30]);
+        assert_eq!(sweep.failures.len(), 1);
+        assert_eq!(sweep.failures[0].to_string(), "2: worker panicked, grid point 2 failed");
+        assert!(matches!(sweep.finish(), Err(SweepError::Incomplete(f)) if f.len() == 1));
+    }
+
+    #[test]
+    fn test_replica_seed() {
+        assert_eq!(replica_seed(1, &[1.5], 3), replica_seed(1, &[1.5], 3));
+        assert_ne!(replica_seed(1, &[1.5], 3), replica_seed(1, &[1.5], 4));
+        assert_ne!(replica_seed(1, &[1.5], 3), replica_seed(1, &[1.51], 3));
+        assert_ne!(replica_seed(1, &[1.5, 0.5], 3), replica_seed(1, &[0.5, 1.5], 3));
+        assert_ne!(replica_seed(1, &[1.5], 3), replica_seed(2, &[1.5], 3));
+    }
+}
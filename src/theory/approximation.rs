@@ -0,0 +1,157 @@
+use serde::Deserialize;
+
+use super::ode::{dormand_prince, System};
+
+const TOLERANCE: f64 = 1e-10;
+
+/// Time the equations are integrated to reach the stationary state.
+const T_STATIONARY: f64 = 1e4;
+
+/// Initial density, the pairs start uncorrelated.
+const RHO_0: f64 = 0.5;
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Topology {
+    /// Nearest neighbours with rate lambda each, like `sim1d`.
+    OneD,
+    /// Nearest neighbours with rate lambda * alpha and next nearest with lambda * (1 - alpha), like `sim1d_b`.
+    OneDB,
+    /// Horizontal neighbours with rate lambda * alpha and vertical with lambda * (1 - alpha), like `sim2d`.
+    TwoD,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Approximation {
+    MeanField,
+    Pair,
+}
+
+impl Approximation {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Approximation::MeanField => "mean field",
+            Approximation::Pair => "pair",
+        }
+    }
+}
+
+/// Neighbours of a site grouped into classes of equal infection rate,
+/// every class is a pair of opposite neighbours.
+fn edge_classes(topology: Topology, lambda: f64, alpha: f64) -> Vec<f64> {
+    match topology {
+        Topology::OneD => vec![lambda],
+        Topology::OneDB | Topology::TwoD => vec![lambda * alpha, lambda * (1.0 - alpha)],
+    }
+}
+
+const NEIGHBOURS_PER_CLASS: f64 = 2.0;
+
+struct MeanField {
+    rates: Vec<f64>,
+}
+
+impl System for MeanField {
+    fn derivative(&self, y: &[f64], dy: &mut [f64]) {
+        let rho = y[0];
+        let total: f64 = self.rates.iter().map(|r| NEIGHBOURS_PER_CLASS * r).sum();
+        dy[0] = -rho + total * rho * (1.0 - rho);
+    }
+}
+
+/// y[0] is the density, y[1 + c] the probability of an infected pair along class c.
+struct Pair {
+    rates: Vec<f64>,
+}
+
+impl System for Pair {
+    fn derivative(&self, y: &[f64], dy: &mut [f64]) {
+        let rho = y[0];
+        let healthy = (1.0 - rho).max(f64::MIN_POSITIVE);
+        let mixed: Vec<f64> = (0..self.rates.len()).map(|c| rho - y[1 + c]).collect();
+
+        dy[0] = -rho + self.rates.iter().zip(&mixed).map(|(r, m)| NEIGHBOURS_PER_CLASS * r * m).sum::<f64>();
+        for c in 0..self.rates.len() {
+            //infection of the healthy site of a mixed pair by its partner or by its other neighbours
+            let others: f64 = self.rates.iter().zip(&mixed).enumerate()
+                .map(|(c2, (r, m))| {
+                    let neighbours = NEIGHBOURS_PER_CLASS - if c2 == c { 1.0 } else { 0.0 };
+                    neighbours * r * m / healthy
+                })
+                .sum();
+            dy[1 + c] = -2.0 * y[1 + c] + 2.0 * mixed[c] * (self.rates[c] + others);
+        }
+    }
+}
+
+/// Stationary density reached from a partially infected lattice.
+pub fn stationary_density(approximation: Approximation, topology: Topology, lambda: f64, alpha: f64) -> f64 {
+    let rates = edge_classes(topology, lambda, alpha);
+    match approximation {
+        Approximation::MeanField => {
+            dormand_prince(&MeanField { rates }, &[RHO_0], T_STATIONARY, TOLERANCE)[0]
+        },
+        Approximation::Pair => {
+            let mut y0 = vec![RHO_0];
+            y0.extend(rates.iter().map(|_| RHO_0 * RHO_0));
+            dormand_prince(&Pair { rates }, &y0, T_STATIONARY, TOLERANCE)[0]
+        },
+    }
+}
+
+/// Linear stability of the absorbing state: mean field becomes unstable at
+/// sum q_c beta_c = 1 and the pair approximation at sum q_c beta_c / (1 + beta_c) = 1.
+fn growth(approximation: Approximation, rates: &[f64]) -> f64 {
+    match approximation {
+        Approximation::MeanField => rates.iter().map(|r| NEIGHBOURS_PER_CLASS * r).sum(),
+        Approximation::Pair => rates.iter().map(|r| NEIGHBOURS_PER_CLASS * r / (1.0 + r)).sum(),
+    }
+}
+
+/// Critical lambda found by bisection, `None` if no lambda makes the absorbing state unstable.
+pub fn threshold(approximation: Approximation, topology: Topology, alpha: f64) -> Option<f64> {
+    let (mut lower, mut upper) = (0.0, 1.0);
+    while growth(approximation, &edge_classes(topology, upper, alpha)) < 1.0 {
+        upper *= 2.0;
+        if upper > 1e6 {
+            return None;
+        }
+    }
+    for _ in 0..100 {
+        let mid = (lower + upper) / 2.0;
+        if growth(approximation, &edge_classes(topology, mid, alpha)) < 1.0 {
+            lower = mid;
+        } else {
+            upper = mid;
+        }
+    }
+    Some((lower + upper) / 2.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thresholds() {
+        let mean_field = threshold(Approximation::MeanField, Topology::OneD, 1.0).unwrap();
+        let pair = threshold(Approximation::Pair, Topology::OneD, 1.0).unwrap();
+        assert!((mean_field - 0.5).abs() < 1e-9);
+        assert!((pair - 1.0).abs() < 1e-9);
+        // isotropic square lattice: q = 4 and beta = lambda / 2, so lambda_c = 2 / 3
+        let pair = threshold(Approximation::Pair, Topology::TwoD, 0.5).unwrap();
+        assert!((pair - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_stationary_density() {
+        // mean field: rho = 1 - 1 / (2 lambda), pair approximation on a chain: rho = 2 (lambda - 1) / (2 lambda - 1)
+        let rho = stationary_density(Approximation::MeanField, Topology::OneD, 2.0, 1.0);
+        assert!((rho - 0.75).abs() < 1e-6);
+        let rho = stationary_density(Approximation::Pair, Topology::OneD, 2.0, 1.0);
+        assert!((rho - 2.0 / 3.0).abs() < 1e-6);
+        let rho = stationary_density(Approximation::Pair, Topology::OneD, 0.8, 1.0);
+        assert!(rho < 1e-6);
+    }
+}
@@ -0,0 +1,305 @@
+use crate::simulation::finite::neighbour_rates;
+
+/// Configurations are bit masks, so the number of sites is limited by the 2^N states kept in memory.
+pub const MAX_SITES: usize = 20;
+
+/// Configuration with only site 0 infected, the start of `finite::Simulation::new_single_seed`.
+#[cfg(test)]
+pub const SINGLE_SEED: usize = 1;
+
+const TOLERANCE: f64 = 1e-12;
+
+/// Iterations of the power and Gauss-Seidel iterations before giving up.
+const MAX_ITERATIONS: usize = 1_000_000;
+
+/// Largest mean of the Poisson distribution in one uniformization step, larger
+/// time steps are split so that e^-mean does not underflow.
+const MAX_POISSON_MEAN: f64 = 50.0;
+
+/// Exact solution of the contact process on the periodic ring or torus of `finite::Simulation`.
+/// The configurations are the 2^N subsets of infected sites and the generator of the
+/// continuous time Markov chain is applied on the fly instead of being stored.
+/// The empty configuration 0 is the only absorbing state.
+pub struct MasterEquation {
+    sites: usize,
+    /// For every site the sites that infect it and the rates.
+    incoming: Vec<Vec<(usize, f64)>>,
+    /// Largest exit rate of any configuration, the rate of the uniformized chain.
+    max_rate: f64,
+}
+
+pub struct QuasiStationary {
+    /// Probability of every configuration conditioned on survival, index 0 is unused.
+    pub distribution: Vec<f64>,
+    /// Rate at which the surviving probability decays, the inverse of the mean
+    /// absorption time when starting from the quasi-stationary distribution.
+    pub decay_rate: f64,
+}
+
+impl QuasiStationary {
+    /// Mean density of infected sites in the quasi-stationary state.
+    pub fn density(&self) -> f64 {
+        let sites = self.distribution.len().trailing_zeros();
+        self.distribution.iter().enumerate()
+            .map(|(s, p)| p * s.count_ones() as f64)
+            .sum::<f64>() / sites as f64
+    }
+}
+
+impl MasterEquation {
+    /// Returns `None` if the lattice has more than `MAX_SITES` sites.
+    pub fn new(l: usize, dimensions: usize, lambda: f64, alpha: f64) -> Option<Self> {
+        let outgoing = neighbour_rates(l, dimensions, lambda, alpha);
+        let sites = outgoing.len();
+        if sites > MAX_SITES {
+            return None;
+        }
+        let mut incoming = vec![Vec::new(); sites];
+        for (site, targets) in outgoing.into_iter().enumerate() {
+            for (target, rate) in targets {
+                incoming[target].push((site, rate));
+            }
+        }
+        let mut equation = MasterEquation { sites, incoming, max_rate: 0.0 };
+        equation.max_rate = (1..equation.states())
+            .map(|s| equation.exit_rate(s))
+            .fold(0.0, f64::max);
+        Some(equation)
+    }
+
+    pub fn states(&self) -> usize {
+        1 << self.sites
+    }
+
+    /// Configuration with every site infected, the start of `finite::Simulation::with_seed`.
+    pub fn all_infected(&self) -> usize {
+        self.states() - 1
+    }
+
+    /// Calls `f(target, rate)` for every transition out of configuration `s`.
+    fn for_each_transition<F: FnMut(usize, f64)>(&self, s: usize, mut f: F) {
+        for site in 0..self.sites {
+            let bit = 1 << site;
+            if s & bit != 0 {
+                f(s ^ bit, 1.0);
+            } else {
+                let rate: f64 = self.incoming[site].iter()
+                    .filter(|&&(source, _)| s & (1 << source) != 0)
+                    .map(|&(_, rate)| rate)
+                    .sum();
+                if rate > 0.0 {
+                    f(s | bit, rate);
+                }
+            }
+        }
+    }
+
+    fn exit_rate(&self, s: usize) -> f64 {
+        let mut total = 0.0;
+        self.for_each_transition(s, |_, rate| total += rate);
+        total
+    }
+
+    /// One step of the uniformized chain, `p (I + Q / max_rate)`.
+    fn jump(&self, p: &[f64]) -> Vec<f64> {
+        let mut next = vec![0.0; p.len()];
+        next[0] = p[0];
+        for s in 1..p.len() {
+            if p[s] == 0.0 {
+                continue;
+            }
+            let mut stay = 1.0;
+            self.for_each_transition(s, |target, rate| {
+                let fraction = rate / self.max_rate;
+                next[target] += p[s] * fraction;
+                stay -= fraction;
+            });
+            next[s] += p[s] * stay;
+        }
+        next
+    }
+
+    /// Evolves the distribution `p` over a time `dt` by uniformization.
+    fn advance(&self, p: Vec<f64>, dt: f64) -> Vec<f64> {
+        let chunks = (self.max_rate * dt / MAX_POISSON_MEAN).ceil().max(1.0) as usize;
+        let mean = self.max_rate * dt / chunks as f64;
+        let mut p = p;
+        for _ in 0..chunks {
+            let mut weight = (-mean).exp();
+            let mut cumulative = weight;
+            let mut term = p;
+            let mut result: Vec<f64> = term.iter().map(|x| weight * x).collect();
+            let mut k = 0;
+            while 1.0 - cumulative > TOLERANCE && k < MAX_ITERATIONS {
+                k += 1;
+                term = self.jump(&term);
+                weight *= mean / k as f64;
+                cumulative += weight;
+                for (r, x) in result.iter_mut().zip(&term) {
+                    *r += weight * x;
+                }
+            }
+            p = result;
+        }
+        p
+    }
+
+    /// Probability that the infection starting from configuration `initial` survives
+    /// until each of the ascending `times`. One minus it is the distribution of the absorption time.
+    pub fn survival(&self, initial: usize, times: &[f64]) -> Vec<f64> {
+        let mut p = vec![0.0; self.states()];
+        p[initial] = 1.0;
+        let mut t = 0.0;
+        times.iter().map(|&time| {
+            p = self.advance(std::mem::take(&mut p), time - t);
+            t = time;
+            1.0 - p[0]
+        }).collect()
+    }
+
+    /// Mean absorption time from configuration `initial` by Gauss-Seidel iteration of
+    /// `tau(s) = (1 + sum rate(s -> s') tau(s')) / exit_rate(s)`. The iteration converges
+    /// slowly when the absorption time is long, `None` if it did not converge.
+    pub fn mean_absorption_time(&self, initial: usize) -> Option<f64> {
+        let exit_rates: Vec<f64> = (0..self.states()).map(|s| self.exit_rate(s)).collect();
+        let mut tau = vec![0.0; self.states()];
+        for _ in 0..MAX_ITERATIONS {
+            let mut change: f64 = 0.0;
+            for s in 1..self.states() {
+                let mut sum = 1.0;
+                self.for_each_transition(s, |target, rate| sum += rate * tau[target]);
+                let new = sum / exit_rates[s];
+                change = change.max(((new - tau[s]) / new).abs());
+                tau[s] = new;
+            }
+            if change < TOLERANCE {
+                return Some(tau[initial]);
+            }
+        }
+        None
+    }
+
+    /// Quasi-stationary distribution by power iteration of the uniformized chain restricted
+    /// to the surviving configurations, `None` if it did not converge.
+    pub fn quasi_stationary(&self) -> Option<QuasiStationary> {
+        let transient = (self.states() - 1) as f64;
+        let mut p = vec![1.0 / transient; self.states()];
+        p[0] = 0.0;
+        for _ in 0..MAX_ITERATIONS {
+            let mut next = self.jump(&p);
+            next[0] = 0.0;
+            let norm: f64 = next.iter().sum();
+            let mut change = 0.0;
+            for (n, old) in next.iter_mut().zip(&p) {
+                *n /= norm;
+                change += (*n - old).abs();
+            }
+            p = next;
+            if change < TOLERANCE {
+                return Some(self.quasi_stationary_from(p));
+            }
+        }
+        None
+    }
+
+    /// The probability flux into the absorbing state only comes from configurations with one
+    /// infected site healing with rate 1, which avoids the cancellation in 1 - norm.
+    fn quasi_stationary_from(&self, distribution: Vec<f64>) -> QuasiStationary {
+        let decay_rate = (0..self.sites).map(|site| distribution[1 << site]).sum();
+        QuasiStationary { distribution, decay_rate }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simulation::finite::Simulation;
+    use crate::simulation::sim::Sim;
+    use crate::sweep::replica_seed;
+
+    const RUNS: usize = 4000;
+
+    /// Base seed of the Monte Carlo runs, so the comparisons are reproducible.
+    const SEED: u64 = 36;
+
+    /// Extinction times of Monte Carlo runs on the same lattice, starting from every site
+    /// infected or from `SINGLE_SEED`.
+    fn monte_carlo(l: usize, dimensions: usize, lambda: f64, alpha: f64, single_seed: bool) -> Vec<f64> {
+        (0..RUNS).map(|run| {
+            let seed = replica_seed(SEED, &[], run);
+            let mut sim = if single_seed {
+                Simulation::new_single_seed(l, dimensions, lambda, alpha, seed)
+            } else {
+                Simulation::with_seed(l, dimensions, lambda, alpha, seed)
+            };
+            while sim.step() {}
+            sim.get_t()
+        }).collect()
+    }
+
+    fn compare(l: usize, dimensions: usize, lambda: f64, alpha: f64, single_seed: bool) {
+        let exact = MasterEquation::new(l, dimensions, lambda, alpha).unwrap();
+        let initial = if single_seed { SINGLE_SEED } else { exact.all_infected() };
+        let times = monte_carlo(l, dimensions, lambda, alpha, single_seed);
+        let runs = times.len() as f64;
+
+        let mean = times.iter().sum::<f64>() / runs;
+        let variance = times.iter().map(|t| (t - mean).powi(2)).sum::<f64>() / (runs - 1.0);
+        let expected = exact.mean_absorption_time(initial).unwrap();
+        assert!((mean - expected).abs() < 5.0 * (variance / runs).sqrt(), "mean {} expected {}", mean, expected);
+
+        let t = expected;
+        let surviving = times.iter().filter(|&&time| time > t).count() as f64 / runs;
+        let expected = exact.survival(initial, &[t])[0];
+        let std_err = (expected * (1.0 - expected) / runs).sqrt();
+        assert!((surviving - expected).abs() < 5.0 * std_err, "survival {} expected {}", surviving, expected);
+    }
+
+    #[test]
+    fn test_ring_against_monte_carlo() {
+        compare(6, 1, 2.0, 1.0, false);
+        compare(6, 1, 2.0, 1.0, true);
+    }
+
+    #[test]
+    fn test_torus_against_monte_carlo() {
+        compare(3, 2, 1.0, 0.7, false);
+    }
+
+    #[test]
+    fn test_single_seed_without_infection() {
+        let exact = MasterEquation::new(4, 1, 0.0, 1.0).unwrap();
+        let survival = exact.survival(SINGLE_SEED, &[0.5, 2.0]);
+        assert!((survival[0] - (-0.5f64).exp()).abs() < 1e-10);
+        assert!((survival[1] - (-2f64).exp()).abs() < 1e-10);
+        assert!((exact.mean_absorption_time(SINGLE_SEED).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quasi_stationary_decay() {
+        let exact = MasterEquation::new(6, 1, 2.5, 1.0).unwrap();
+        let qsd = exact.quasi_stationary().unwrap();
+        let survival = exact.survival(exact.all_infected(), &[100.0, 110.0]);
+        let decay_rate = (survival[0] / survival[1]).ln() / 10.0;
+        assert!((decay_rate - qsd.decay_rate).abs() < 1e-6 * qsd.decay_rate);
+        assert!(qsd.density() > 0.0 && qsd.density() < 1.0);
+    }
+
+    #[test]
+    fn test_quasi_stationary_distribution() {
+        let exact = MasterEquation::new(3, 2, 1.5, 0.7).unwrap();
+        let qsd = exact.quasi_stationary().unwrap();
+        let p = &qsd.distribution;
+        assert_eq!(p[0], 0.0);
+        assert!(p.iter().all(|&x| x >= 0.0));
+        assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+
+        //left eigenvector of the generator restricted to the surviving configurations,
+        //p Q = -decay_rate p with p Q = (jump(p) - p) max_rate
+        let next = exact.jump(p);
+        for s in 1..p.len() {
+            let flux = (next[s] - p[s]) * exact.max_rate;
+            assert!((flux + qsd.decay_rate * p[s]).abs() < 1e-9, "configuration {}: {} vs {}", s, flux, -qsd.decay_rate * p[s]);
+        }
+    }
+}
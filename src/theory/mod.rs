@@ -0,0 +1,3 @@
+pub mod ode;
+pub mod approximation;
+pub mod master;
@@ -0,0 +1,77 @@
+/// Right hand side of an autonomous system `dy/dt = f(y)`, writes the derivative into `dy`.
+pub trait System {
+    fn derivative(&self, y: &[f64], dy: &mut [f64]);
+}
+
+fn add_scaled(y: &[f64], terms: &[(&[f64], f64)], h: f64) -> Vec<f64> {
+    (0..y.len())
+        .map(|i| y[i] + h * terms.iter().map(|(k, c)| c * k[i]).sum::<f64>())
+        .collect()
+}
+
+/// Integrates with the adaptive Dormand-Prince 5(4) method, the step size is chosen
+/// so that the estimated local error stays below `tolerance`.
+pub fn dormand_prince<S: System>(system: &S, y0: &[f64], t_max: f64, tolerance: f64) -> Vec<f64> {
+    const C: [[f64; 6]; 6] = [
+        [1.0 / 5.0, 0.0, 0.0, 0.0, 0.0, 0.0],
+        [3.0 / 40.0, 9.0 / 40.0, 0.0, 0.0, 0.0, 0.0],
+        [44.0 / 45.0, -56.0 / 15.0, 32.0 / 9.0, 0.0, 0.0, 0.0],
+        [19372.0 / 6561.0, -25360.0 / 2187.0, 64448.0 / 6561.0, -212.0 / 729.0, 0.0, 0.0],
+        [9017.0 / 3168.0, -355.0 / 33.0, 46732.0 / 5247.0, 49.0 / 176.0, -5103.0 / 18656.0, 0.0],
+        [35.0 / 384.0, 0.0, 500.0 / 1113.0, 125.0 / 192.0, -2187.0 / 6784.0, 11.0 / 84.0],
+    ];
+    const ERROR: [f64; 7] = [
+        71.0 / 57600.0, 0.0, -71.0 / 16695.0, 71.0 / 1920.0, -17253.0 / 339200.0, 22.0 / 525.0, -1.0 / 40.0,
+    ];
+
+    let n = y0.len();
+    let mut y = y0.to_vec();
+    let mut t = 0.0;
+    let mut h = (t_max / 100.0).min(0.1);
+    let mut k = vec![vec![0.0; n]; 7];
+    while t < t_max {
+        h = h.min(t_max - t);
+        system.derivative(&y, &mut k[0]);
+        for stage in 0..6 {
+            let terms: Vec<(&[f64], f64)> = (0..=stage).map(|j| (k[j].as_slice(), C[stage][j])).collect();
+            let y_stage = add_scaled(&y, &terms, h);
+            system.derivative(&y_stage, &mut k[stage + 1]);
+        }
+        let terms: Vec<(&[f64], f64)> = (0..6).map(|j| (k[j].as_slice(), C[5][j])).collect();
+        let y_next = add_scaled(&y, &terms, h);
+        let error = (0..n)
+            .map(|i| (h * (0..7).map(|j| ERROR[j] * k[j][i]).sum::<f64>()).abs())
+            .fold(0.0, f64::max);
+
+        if error <= tolerance {
+            t += h;
+            y = y_next;
+        }
+        let factor = if error == 0.0 { 5.0 } else { 0.9 * (tolerance / error).powf(0.2) };
+        h *= factor.clamp(0.2, 5.0);
+    }
+    y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Decay;
+
+    impl System for Decay {
+        fn derivative(&self, y: &[f64], dy: &mut [f64]) {
+            dy[0] = -y[0];
+            dy[1] = y[0] - y[1];
+        }
+    }
+
+    #[test]
+    fn test_decay() {
+        // y0 = e^-t, y1 = t e^-t
+        let expected = [(-2f64).exp(), 2.0 * (-2f64).exp()];
+        let y = dormand_prince(&Decay, &[1.0, 0.0], 2.0, 1e-10);
+        assert!((y[0] - expected[0]).abs() < 1e-8);
+        assert!((y[1] - expected[1]).abs() < 1e-8);
+    }
+}